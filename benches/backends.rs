@@ -0,0 +1,88 @@
+//! Benchmarks for the hot path of each backend.
+//!
+//! Run with `cargo bench`; results are only comparable between runs on the same machine.
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// A typical `login.defs`, mostly made up of comments.
+#[cfg(not(windows))]
+const LOGIN_DEFS: &str = "\
+#
+# /etc/login.defs - Configuration control definitions for the shadow package.
+#
+MAIL_DIR        /var/spool/mail
+#
+# Password aging controls:
+#
+#\tPASS_MAX_DAYS\tMaximum number of days a password may be used.
+#\tPASS_MIN_DAYS\tMinimum number of days allowed between password changes.
+#\tPASS_WARN_AGE\tNumber of days warning given before a password expires.
+#
+PASS_MAX_DAYS\t99999
+PASS_MIN_DAYS\t0
+PASS_WARN_AGE\t7
+
+#
+# Min/max values for automatic uid selection in useradd
+#
+UID_MIN\t\t\t 1000
+UID_MAX\t\t\t60000
+# System accounts
+SYS_UID_MIN\t\t  201
+SYS_UID_MAX\t\t  999
+# Extra per user uids
+SUB_UID_MIN\t\t   100000
+SUB_UID_MAX\t\t600100000
+SUB_UID_COUNT\t\t    65536
+
+#
+# Min/max values for automatic gid selection in groupadd
+#
+GID_MIN\t\t\t 1000
+GID_MAX\t\t\t60000
+
+UMASK\t\t077
+HOME_MODE\t0700
+USERGROUPS_ENAB yes
+ENCRYPT_METHOD YESCRYPT
+";
+
+#[cfg(not(windows))]
+fn shadow(c: &mut Criterion) {
+    use omst::shadow;
+    use std::fs;
+    use std::hint::black_box;
+
+    let root = std::env::temp_dir().join(format!("omst-bench-{}", std::process::id()));
+    fs::create_dir_all(root.join("etc")).unwrap();
+    fs::write(root.join("etc/login.defs"), LOGIN_DEFS).unwrap();
+    let range = shadow::uid_range_in(&root).unwrap();
+
+    c.bench_function("shadow/parse", |b| {
+        b.iter(|| shadow::uid_range_in(black_box(&root)))
+    });
+    c.bench_function("shadow/uncached", |b| {
+        b.iter(|| shadow::of_uid_in(black_box(&root), black_box(1000)))
+    });
+    c.bench_function("shadow/cached", |b| {
+        b.iter(|| shadow::classify(black_box(1000), black_box(&range)))
+    });
+    c.bench_function("shadow/euid", |b| b.iter(shadow::euid));
+    c.bench_function("shadow/omst", |b| b.iter(omst::omst));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[cfg(windows)]
+fn winapi(c: &mut Criterion) {
+    use omst::winapi;
+
+    c.bench_function("winapi/net_api", |b| b.iter(winapi::omst));
+    c.bench_function("winapi/token", |b| b.iter(winapi::token_priv));
+    c.bench_function("winapi/omst", |b| b.iter(omst::omst));
+}
+
+#[cfg(not(windows))]
+criterion_group!(benches, shadow);
+#[cfg(windows)]
+criterion_group!(benches, winapi);
+criterion_main!(benches);
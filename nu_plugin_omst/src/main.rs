@@ -0,0 +1,83 @@
+//! Nushell plugin which reveals whomst thou art as a record.
+//!
+//! After registering the plugin with `plugin add nu_plugin_omst`, the `omst` command returns a
+//! record with the `symbol`, `level`, `backend`, and `error` fields, like `omst --json`.
+use nu_plugin::{
+    serve_plugin, EngineInterface, EvaluatedCall, MsgPackSerializer, Plugin, PluginCommand,
+    SimplePluginCommand,
+};
+use nu_protocol::{record, Category, LabeledError, Signature, SyntaxShape, Type, Value};
+use omst::Permissions;
+
+/// The plugin itself.
+struct OmstPlugin;
+impl Plugin for OmstPlugin {
+    fn version(&self) -> String {
+        env!("CARGO_PKG_VERSION").into()
+    }
+
+    fn commands(&self) -> Vec<Box<dyn PluginCommand<Plugin = Self>>> {
+        vec![Box::new(Omst)]
+    }
+}
+
+/// The `omst` command.
+struct Omst;
+impl SimplePluginCommand for Omst {
+    type Plugin = OmstPlugin;
+
+    fn name(&self) -> &str {
+        "omst"
+    }
+
+    fn description(&self) -> &str {
+        "Reveals whomst thou art."
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build(self.name())
+            .optional(
+                "user",
+                SyntaxShape::String,
+                "User to classify instead of the current one.",
+            )
+            .input_output_type(Type::Nothing, Type::Any)
+            .category(Category::System)
+    }
+
+    fn run(
+        &self,
+        _plugin: &OmstPlugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        _input: &Value,
+    ) -> Result<Value, LabeledError> {
+        let span = call.head;
+        let user: Option<String> = call.opt(0)?;
+        let result = match &user {
+            Some(user) => omst::of_user(user),
+            None => omst::omst(),
+        };
+        let level = result.as_ref().ok().copied();
+        let symbol = level.map_or('?', Permissions::be);
+        Ok(Value::record(
+            record! {
+                "symbol" => Value::string(symbol.to_string(), span),
+                "level" => match level {
+                    Some(level) => Value::string(level.to_string(), span),
+                    None => Value::nothing(span),
+                },
+                "backend" => Value::string(omst::backend_name(), span),
+                "error" => match &result {
+                    Ok(_) => Value::nothing(span),
+                    Err(err) => Value::string(err.to_string(), span),
+                },
+            },
+            span,
+        ))
+    }
+}
+
+fn main() {
+    serve_plugin(&OmstPlugin, MsgPackSerializer)
+}
@@ -0,0 +1,30 @@
+//! Exposes build information for `--version`.
+use std::env;
+
+fn main() {
+    let target = env::var("TARGET").unwrap();
+    let backend = if env::var_os("CARGO_FEATURE_STUB").is_some() {
+        "stub"
+    } else if env::var_os("CARGO_CFG_WINDOWS").is_some() {
+        "winapi"
+    } else {
+        "shadow"
+    };
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            let feature = key.strip_prefix("CARGO_FEATURE_")?;
+            Some(feature.to_ascii_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    let features = if features.is_empty() {
+        "none".to_owned()
+    } else {
+        features.join(", ")
+    };
+
+    println!("cargo:rustc-env=OMST_TARGET={target}");
+    println!("cargo:rustc-env=OMST_BACKEND={backend}");
+    println!("cargo:rustc-env=OMST_FEATURES={features}");
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,41 @@
+# German translations of backend errors and `--explain` output.
+#
+# Messages use a subset of Fluent syntax: one `id = message` per line, with `{ $name }` placeables.
+# Missing messages fall back to English.
+
+error-login-defs-open = { $path } konnte nicht geöffnet werden: { $error }
+error-login-defs-read = { $path } konnte nicht gelesen werden: { $error }
+error-file-too-large = { $path } ist mindestens { $max } Bytes lang
+error-line-too-long = Zeile { $line } von { $path } ist länger als { $max } Bytes
+error-def-missing = { $def } fehlt in { $path }
+error-def-empty = { $def } ist in { $path } leer
+error-def-invalid = { $def } in { $path } ist keine gültige { $id } ({ $value })
+error-def-out-of-range = { $def } in { $path } ist größer als die größte { $id }, { $max } ({ $value })
+error-stat = { $path } konnte nicht abgefragt werden: { $error }
+error-unmapped = der Besitzer von { $path } ist keinem lokalen Benutzer zugeordnet
+error-unknown-user = der Benutzer { $name } existiert nicht
+error-lookup-user = der Benutzer { $name } konnte nicht nachgeschlagen werden: { $error }
+error-process = der Besitzer des Prozesses { $pid } konnte nicht ermittelt werden: { $error }
+error-peer = die Anmeldedaten des Socket-Partners konnten nicht ermittelt werden: { $error }
+
+reason-missing = { $path } fehlt
+reason-unreadable = { $path } ist nicht lesbar ({ $error })
+
+explain-root = UID { $uid } ist root
+explain-home = UID { $uid } hat das Home-Verzeichnis { $home }, geraten, da { $reason } (geringe Zuverlässigkeit)
+explain-nobody = UID { $uid } ist üblicherweise nobody, trotz UID_MAX({ $max })
+explain-below = UID { $uid } liegt unter UID_MIN({ $min })
+explain-above = UID { $uid } liegt über UID_MAX({ $max })
+explain-within = UID { $uid } liegt innerhalb von UID_MIN({ $min })..UID_MAX({ $max })
+explain-from-defaults = { $trace } laut den Standardwerten, da { $reason }
+explain-from-login-defs = { $trace } laut { $path }
+explain-from-lines = { $trace } laut { $path } (UID_MIN { $min }; UID_MAX { $max })
+explain-login-uid = { $trace }, nach Anmeldung als UID { $login }
+explain-tty-owner = { $trace }, auf einem Terminal von UID { $owner }
+explain-admin-group = { $trace }, und kann als Mitglied von { $group } Rechte erhöhen
+explain-result = { $trace } → { $level } `{ $symbol }`
+
+origin-default = Standardwert
+origin-line = in Zeile { $line }
+origin-line-ignoring-one = in Zeile { $line }, eine weitere Definition wird ignoriert
+origin-line-ignoring = in Zeile { $line }, { $count } weitere Definitionen werden ignoriert
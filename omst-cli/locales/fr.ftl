@@ -0,0 +1,41 @@
+# French translations of backend errors and `--explain` output.
+#
+# Messages use a subset of Fluent syntax: one `id = message` per line, with `{ $name }` placeables.
+# Missing messages fall back to English.
+
+error-login-defs-open = impossible d'ouvrir { $path } : { $error }
+error-login-defs-read = impossible de lire { $path } : { $error }
+error-file-too-large = { $path } fait au moins { $max } octets
+error-line-too-long = la ligne { $line } de { $path } dépasse { $max } octets
+error-def-missing = { $def } est absent de { $path }
+error-def-empty = { $def } est vide dans { $path }
+error-def-invalid = { $def } dans { $path } n'est pas un { $id } valide ({ $value })
+error-def-out-of-range = { $def } dans { $path } dépasse le plus grand { $id }, { $max } ({ $value })
+error-stat = impossible d'examiner { $path } : { $error }
+error-unmapped = le propriétaire de { $path } ne correspond à aucun utilisateur local
+error-unknown-user = l'utilisateur { $name } n'existe pas
+error-lookup-user = impossible de rechercher l'utilisateur { $name } : { $error }
+error-process = impossible de déterminer le propriétaire du processus { $pid } : { $error }
+error-peer = impossible de déterminer les identifiants du pair du socket : { $error }
+
+reason-missing = { $path } est absent
+reason-unreadable = { $path } est illisible ({ $error })
+
+explain-root = l'UID { $uid } est root
+explain-home = l'UID { $uid } a le répertoire personnel { $home }, deviné car { $reason } (confiance faible)
+explain-nobody = l'UID { $uid } est conventionnellement nobody, malgré UID_MAX({ $max })
+explain-below = l'UID { $uid } est inférieur à UID_MIN({ $min })
+explain-above = l'UID { $uid } est supérieur à UID_MAX({ $max })
+explain-within = l'UID { $uid } est compris dans UID_MIN({ $min })..UID_MAX({ $max })
+explain-from-defaults = { $trace } d'après les valeurs par défaut, car { $reason }
+explain-from-login-defs = { $trace } d'après { $path }
+explain-from-lines = { $trace } d'après { $path } (UID_MIN { $min } ; UID_MAX { $max })
+explain-login-uid = { $trace }, après connexion en tant qu'UID { $login }
+explain-tty-owner = { $trace }, sur un terminal appartenant à l'UID { $owner }
+explain-admin-group = { $trace }, et peut élever ses privilèges en tant que membre de { $group }
+explain-result = { $trace } → { $level } `{ $symbol }`
+
+origin-default = par défaut
+origin-line = à la ligne { $line }
+origin-line-ignoring-one = à la ligne { $line }, en ignorant 1 autre définition
+origin-line-ignoring = à la ligne { $line }, en ignorant { $count } autres définitions
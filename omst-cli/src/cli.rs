@@ -0,0 +1,2204 @@
+//! Command-line interface shared by `omst` and `omst-be`.
+use crate::config::{self, ColorChoice, Config, Format, GlyphSet};
+use crate::doctor;
+#[cfg(feature = "i18n")]
+use crate::i18n;
+use crate::init::{self, InitShell};
+#[cfg(unix)]
+use crate::log::{self, LogTarget};
+#[cfg(target_os = "linux")]
+use crate::notify::Watcher;
+use crate::template::{Field, Template};
+use clap::builder::{PossibleValue, PossibleValuesParser, TypedValueParser};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+#[cfg(not(windows))]
+use omst::shadow;
+use omst::{Permissions, Report};
+use std::env;
+use std::error::Error as StdError;
+use std::fmt;
+#[cfg(unix)]
+use std::fs;
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Write};
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::fd::FromRawFd;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+#[cfg(not(windows))]
+use std::path::PathBuf;
+use std::process::{self as proc, ExitCode, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Version, backend, and build information printed by `--version`.
+const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    "\nbackend: ",
+    env!("OMST_BACKEND"),
+    "\nfeatures: ",
+    env!("OMST_FEATURES"),
+    "\ntarget: ",
+    env!("OMST_TARGET"),
+);
+
+/// Reveals whomst thou art with a single character.
+#[derive(Parser, Default, Debug)]
+#[command(
+    name = "omst",
+    version,
+    long_version = LONG_VERSION,
+    about,
+    args_conflicts_with_subcommands = true,
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Print the result as a JSON object.
+    #[arg(long)]
+    json: bool,
+
+    /// Print a detailed report of how the result was determined.
+    #[arg(short, long, conflicts_with = "json")]
+    verbose: bool,
+
+    /// Print the name of the level instead of its symbol.
+    #[arg(long, conflicts_with_all = ["json", "verbose"])]
+    name: bool,
+
+    /// Print a single-line trace of how the result was determined.
+    #[arg(long, conflicts_with_all = ["json", "verbose", "name"])]
+    explain: bool,
+
+    /// Print the result as Prometheus metrics, e.g. for node_exporter's textfile collector.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "json", "verbose", "name", "explain", "powerline", "prompt", "watch", "stdin",
+        ],
+    )]
+    metrics: bool,
+
+    /// Print the result as a segment for powerline-go.
+    #[arg(long, conflicts_with_all = ["json", "verbose", "name", "explain", "prompt"])]
+    powerline: bool,
+
+    /// Print the result according to the given template.
+    ///
+    /// Placeholders are `{symbol}`, `{name}` (the name of the level), `{uid}`, `{backend}`, and
+    /// `{error}`; literal braces are written as `{{` and `}}`.
+    #[arg(
+        long = "format",
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["json", "verbose", "name", "explain", "powerline", "metrics"],
+    )]
+    template: Option<Template>,
+
+    /// Print a single value of the result, like a template with one placeholder.
+    #[arg(
+        long,
+        value_name = "FIELD",
+        conflicts_with_all = [
+            "json", "verbose", "name", "explain", "powerline", "metrics", "template", "prompt",
+        ],
+    )]
+    field: Option<Field>,
+
+    /// Classify the given user instead of the current one.
+    #[arg(long, value_name = "USER")]
+    query: Option<String>,
+
+    /// Classify the given user ID, even if no such user exists.
+    #[cfg(not(windows))]
+    #[arg(long, value_name = "N", conflicts_with = "query")]
+    uid: Option<u32>,
+
+    /// Read `login.defs` relative to the given root, instead of the live system.
+    #[cfg(not(windows))]
+    #[arg(long, value_name = "DIR", requires = "uid")]
+    sysroot: Option<PathBuf>,
+
+    /// Classify the owner of the given process.
+    #[cfg(not(windows))]
+    #[arg(long, value_name = "N", conflicts_with_all = ["query", "uid"])]
+    pid: Option<u32>,
+
+    /// Classify a user on the given host instead, by running `omst-be` there over SSH.
+    #[arg(
+        long,
+        value_name = "[USER@]HOST",
+        conflicts_with_all = ["verbose", "explain", "all", "stdin"],
+    )]
+    host: Option<String>,
+
+    /// Give up on determining the result after the given number of milliseconds.
+    #[arg(long, value_name = "MS", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Keep running, reprinting the result whenever it changes.
+    ///
+    /// The result is checked every given number of seconds, or every second by default. On Linux,
+    /// local users are instead rechecked only when the files the result depends on change.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        num_args = 0..=1,
+        default_missing_value = "1",
+        value_parser = parse_interval,
+    )]
+    watch: Option<Duration>,
+
+    /// When to color the output by level [default: auto]
+    ///
+    /// With `auto`, output is colored only if standard output is a terminal and the `NO_COLOR`
+    /// environment variable is unset or empty.
+    #[arg(long, value_name = "WHEN")]
+    color: Option<ColorChoice>,
+
+    /// Escape the output for inclusion in the given shell's prompt.
+    ///
+    /// With `--color=auto`, output is always colored in this mode unless `NO_COLOR` is set, since
+    /// prompts are usually generated via command substitution.
+    #[arg(long, value_name = "SHELL", conflicts_with_all = ["json", "verbose", "explain"])]
+    prompt: Option<PromptShell>,
+
+    /// Print nothing, and exit with a status determined by the level.
+    ///
+    /// The status is 20 for guest, 21 for user, 22 for operator, 23 for system, and 24 for absolute
+    /// permissions; errors use the usual statuses.
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = [
+            "json", "verbose", "name", "explain", "template", "field", "powerline", "metrics", "prompt",
+            "watch", "all", "stdin", "check", "serve", "fallback",
+        ],
+    )]
+    quiet: bool,
+
+    /// Print nothing if an error occurs, only exiting unsuccessfully.
+    #[arg(long, conflicts_with = "all")]
+    strict: bool,
+
+    /// Use a built-in set of symbols for the levels [default: ascii]
+    ///
+    /// When given, this also takes precedence over the replacements in the configuration file.
+    #[arg(long, value_name = "SET")]
+    glyphs: Option<GlyphSet>,
+
+    /// Print the given character if an error occurs, instead of `?` or the error itself.
+    #[arg(long, value_name = "CHAR", conflicts_with = "strict")]
+    fallback: Option<char>,
+
+    /// Don't print a newline after the output.
+    #[arg(short, long)]
+    no_newline: bool,
+
+    /// Print a NUL character after the output instead of a newline.
+    #[arg(short = '0', long, conflicts_with = "no_newline")]
+    null: bool,
+
+    /// Separate the records of `--all` and `--stdin` with the given string instead of a newline.
+    ///
+    /// `newline`, `tab`, and `nul` stand for the respective characters; anything else is used
+    /// literally.
+    #[arg(
+        long,
+        value_name = "SEP",
+        value_parser = parse_separator,
+        requires = "batch",
+        conflicts_with_all = ["no_newline", "null"],
+    )]
+    separator: Option<String>,
+
+    /// Print two symbols: one for the invoking user, and one for the effective user.
+    ///
+    /// The invoking user is the one who ran `sudo`, if we're running as root under it, and the
+    /// real user otherwise.
+    #[cfg(not(windows))]
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "json", "verbose", "name", "explain", "template", "field", "powerline", "metrics", "quiet",
+            "query", "uid", "pid", "host", "watch", "check",
+        ],
+    )]
+    both: bool,
+
+    /// Add a suffix to the symbol when running in a container or chroot.
+    ///
+    /// The suffix is `'`, unless configured otherwise.
+    #[cfg(not(windows))]
+    #[arg(long)]
+    container: bool,
+
+    /// Add a suffix to the symbol when the controlling terminal is owned by another user.
+    ///
+    /// This suggests that the session switched users with `su` or `sudo -i`. The suffix is `^`,
+    /// unless configured otherwise, and is only added for the current user.
+    #[cfg(not(windows))]
+    #[arg(long)]
+    switched: bool,
+
+    /// Print a table of all local accounts and their levels.
+    #[arg(
+        long,
+        group = "batch",
+        conflicts_with_all = [
+            "json", "verbose", "name", "explain", "template", "field", "prompt", "query", "uid", "pid",
+            "watch",
+        ],
+    )]
+    all: bool,
+
+    /// Print the table of `--all` as tab- or comma-separated values, with a header row.
+    #[arg(long, value_name = "FORMAT", requires = "all")]
+    output: Option<TableFormat>,
+
+    /// Classify users read from standard input, one name or user ID per line.
+    #[arg(
+        long,
+        group = "batch",
+        conflicts_with_all = [
+            "verbose", "explain", "query", "uid", "pid", "watch", "all",
+        ],
+    )]
+    stdin: bool,
+
+    /// Print nothing, and exit successfully only if the level is at least the given one.
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        conflicts_with_all = [
+            "json", "verbose", "name", "explain", "template", "field", "prompt", "watch", "all", "stdin",
+        ],
+    )]
+    #[arg(value_parser = level_parser())]
+    check: Option<Permissions>,
+
+    /// Serve the result over HTTP at the given address, like `127.0.0.1:8080`.
+    ///
+    /// `/` responds with the symbol, and `/json` responds with a JSON object; the status is 500 if
+    /// an error occurred.
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["watch", "all", "stdin", "check"])]
+    serve: Option<SocketAddr>,
+
+    /// Serve the result to every client which connects to the given Unix socket.
+    ///
+    /// The socket defaults to `$XDG_RUNTIME_DIR/omst.sock`, and the result is recomputed at most
+    /// once per second, or only when its inputs change if they can be watched.
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_name = "SOCKET",
+        num_args = 0..=1,
+        conflicts_with_all = ["watch", "all", "stdin", "check", "serve"],
+    )]
+    daemon: Option<Option<PathBuf>>,
+
+    /// Serve the result on the D-Bus session bus as `org.omst.Whomst`.
+    ///
+    /// The result is checked like with `--watch`, and the `Changed` signal is emitted whenever it
+    /// changes.
+    #[cfg(all(not(windows), feature = "dbus"))]
+    #[arg(long, conflicts_with_all = ["all", "stdin", "check", "serve", "daemon"])]
+    dbus: bool,
+
+    /// Also send errors to the given system log.
+    #[cfg(unix)]
+    #[arg(long, value_name = "TARGET")]
+    log: Option<LogTarget>,
+
+    /// Suffix added to symbols, if we're in a container or switched users and detection was
+    /// enabled.
+    #[arg(skip)]
+    suffix: Option<String>,
+
+    /// Whether to print tmux style directives, i.e. whether we're `omst tmux`.
+    #[arg(skip)]
+    tmux: bool,
+
+    /// Whether to print shell variable assignments, i.e. whether we're `omst env`.
+    #[arg(skip)]
+    env: bool,
+
+    /// Whether errors should be fully printed, i.e. whether we're `omst-be`.
+    #[arg(skip)]
+    be: bool,
+
+    /// Contents of the configuration file.
+    #[arg(skip)]
+    config: Config,
+}
+
+/// Subcommands, which replace the default behaviour.
+///
+/// Other than `completions`, these are shorthands for the equivalent flags.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the name of the level and full errors, like `omst-be`.
+    Be,
+
+    /// Print a detailed report of how the result was determined, like `--verbose`.
+    Who {
+        /// User to classify instead of the current one.
+        user: Option<String>,
+    },
+
+    /// Print nothing, and exit successfully only if the level is at least the given one, like
+    /// `--check`.
+    Check {
+        /// Minimum level.
+        #[arg(value_parser = level_parser())]
+        level: Permissions,
+
+        /// User to classify instead of the current one.
+        user: Option<String>,
+    },
+
+    /// Print a single-line trace of how the result was determined, like `--explain`.
+    Explain {
+        /// User to classify instead of the current one.
+        user: Option<String>,
+    },
+
+    /// Classify the given user instead of the current one, like `--query`.
+    Query {
+        /// User to classify.
+        user: String,
+    },
+
+    /// Print the symbol wrapped in tmux style directives, for use in `status-right`.
+    Tmux {
+        /// User to classify instead of the current one.
+        user: Option<String>,
+    },
+
+    /// Print shell variable assignments for the symbol, level, and user ID, to be evaluated.
+    Env {
+        /// User to classify instead of the current one.
+        user: Option<String>,
+    },
+
+    /// Print the groups of this process, classified by `GID_MIN` and `GID_MAX`.
+    #[cfg(not(windows))]
+    Groups,
+
+    /// Print the account privileges, token elevation, and integrity level of this process.
+    #[cfg(windows)]
+    Elevation,
+
+    /// Check the prerequisites of the backend, and suggest fixes for any problems.
+    Doctor,
+
+    /// Print shell code which sets `OMST_SYMBOL` before each prompt, to be evaluated at startup.
+    Init {
+        /// Shell to generate code for.
+        shell: InitShell,
+    },
+
+    /// Print a completion script for the given shell.
+    Completions {
+        /// Shell to generate completions for.
+        shell: Shell,
+    },
+}
+
+/// Format for the table of `--all`, for importing into other tools.
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum TableFormat {
+    /// Tab-separated values, with tabs, newlines, and backslashes escaped with backslashes.
+    Tsv,
+
+    /// Comma-separated values, as described by RFC 4180.
+    Csv,
+}
+impl TableFormat {
+    /// Writes a row of the table.
+    fn write_row(self, out: &mut impl Write, row: &[&str], term: &str) -> io::Result<()> {
+        for (i, cell) in row.iter().enumerate() {
+            match (self, i) {
+                (_, 0) => {}
+                (TableFormat::Tsv, _) => out.write_all(b"\t")?,
+                (TableFormat::Csv, _) => out.write_all(b",")?,
+            }
+            match self {
+                TableFormat::Tsv => {
+                    let cell = cell
+                        .replace('\\', "\\\\")
+                        .replace('\t', "\\t")
+                        .replace('\n', "\\n")
+                        .replace('\r', "\\r");
+                    out.write_all(cell.as_bytes())?;
+                }
+                TableFormat::Csv if cell.contains([',', '"', '\n', '\r']) => {
+                    write!(out, "\"{}\"", cell.replace('"', "\"\""))?;
+                }
+                TableFormat::Csv => out.write_all(cell.as_bytes())?,
+            }
+        }
+        out.write_all(term.as_bytes())
+    }
+}
+
+/// Shell whose prompt is being generated.
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum PromptShell {
+    /// Wraps escape sequences in `\001` and `\002`, which is what `\[` and `\]` expand to.
+    Bash,
+
+    /// Wraps escape sequences in `%{` and `%}`, and escapes `%` as `%%`.
+    Zsh,
+
+    /// Leaves escape sequences as-is, since fish measures them itself.
+    Fish,
+}
+
+impl Args {
+    /// Format selected by the arguments or configuration.
+    ///
+    /// `omst` prints symbols by default, whereas `omst-be` prints names.
+    fn format(&self) -> Format {
+        if self.json {
+            Format::Json
+        } else if self.verbose {
+            Format::Verbose
+        } else if self.explain {
+            Format::Explain
+        } else if self.metrics {
+            Format::Metrics
+        } else if self.powerline {
+            Format::Powerline
+        } else if self.tmux {
+            Format::Tmux
+        } else if self.env {
+            Format::Env
+        } else if self.name {
+            Format::Name
+        } else if let Some(format) = self.config.format {
+            format
+        } else if self.be {
+            Format::Name
+        } else {
+            Format::Symbol
+        }
+    }
+
+    /// Terminator printed after the output, or after each record of `--all` and `--stdin`.
+    fn terminator(&self) -> &str {
+        if let Some(separator) = &self.separator {
+            separator
+        } else if self.no_newline {
+            ""
+        } else if self.null {
+            "\0"
+        } else {
+            "\n"
+        }
+    }
+
+    /// Symbol for the given level, or an error.
+    fn glyph(&self, level: Option<Permissions>) -> String {
+        if let (None, Some(fallback)) = (level, self.fallback) {
+            return fallback.to_string();
+        }
+        let mut glyph = match (self.glyphs, self.config.glyphs.get(level)) {
+            (None, Some(glyph)) => glyph.clone(),
+            (set, _) => set
+                .or(self.config.glyph_set)
+                .unwrap_or_default()
+                .glyph(level)
+                .to_owned(),
+        };
+        if let (Some(_), Some(suffix)) = (level, &self.suffix) {
+            glyph.push_str(suffix);
+        }
+        glyph
+    }
+
+    /// Whether output should be colored.
+    fn use_color(&self) -> bool {
+        match self
+            .color
+            .or(self.config.color)
+            .unwrap_or(ColorChoice::Auto)
+        {
+            ColorChoice::Auto => {
+                (self.prompt.is_some() || io::stdout().is_terminal())
+                    && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+
+    /// Determines the permissions of the user, giving up after the timeout.
+    fn omst(&self, subject: &Subject) -> io::Result<Permissions> {
+        self.flatten(self.lookup(subject))
+    }
+
+    /// Shows operators as ordinary users, if the configuration says to.
+    fn flatten(&self, result: io::Result<Permissions>) -> io::Result<Permissions> {
+        match self.config.operator {
+            Some(false) => result.map(Permissions::flatten),
+            _ => result,
+        }
+    }
+
+    /// Classifies a user ID against an already loaded range, for `--all` and `--stdin`, respecting
+    /// the configuration like [`lookup`](Args::lookup) does.
+    #[cfg(not(windows))]
+    fn classify(&self, uid: u32, range: &std::ops::RangeInclusive<u32>) -> Permissions {
+        let result = match self.config.nobody_is_guest {
+            Some(false) => shadow::classify_by_range(uid, range),
+            _ => shadow::classify(uid, range),
+        };
+        shadow::with_user_groups(result, uid)
+    }
+
+    /// Determines the permissions of the user, including operators, giving up after the timeout.
+    fn lookup(&self, subject: &Subject) -> io::Result<Permissions> {
+        if let (Some(path), Subject::Current | Subject::User(_)) = (&self.config.plugin, subject) {
+            return self.plugin(path, subject);
+        }
+        let result = match self.timeout {
+            Some(timeout) => {
+                let subject = subject.clone();
+                omst::with_timeout(timeout, move || subject.omst())
+            }
+            None => subject.omst(),
+        };
+
+        // if `nobody` shouldn't always be a guest, we have to check the range again
+        #[cfg(not(windows))]
+        if let (Some(false), Ok(Permissions::Guest)) = (self.config.nobody_is_guest, &result) {
+            if let Some(uid) = subject.uid().filter(|uid| shadow::NOBODY.contains(uid)) {
+                let range = match subject {
+                    Subject::Sysroot { root, .. } => shadow::uid_range_in(root),
+                    _ => shadow::uid_range(),
+                };
+                // if the range can't be loaded, the result was a fallback, which is kept
+                if let Ok(range) = range {
+                    return Ok(shadow::classify_by_range(uid, &range).into());
+                }
+            }
+        }
+        result
+    }
+
+    /// Determines the permissions of the user with a plugin, giving up after the timeout.
+    #[cfg(feature = "plugins")]
+    fn plugin(&self, path: &Path, subject: &Subject) -> io::Result<Permissions> {
+        let (path, subject) = (path.to_owned(), subject.clone());
+        let lookup = move || {
+            // the plugin was configured by the user, so it's trusted as much as the configuration
+            let plugin = unsafe { omst::plugin::Plugin::load(&path) }?;
+            match subject {
+                Subject::User(name) => plugin.of_user(&name),
+                _ => plugin.omst(),
+            }
+        };
+        match self.timeout {
+            Some(timeout) => omst::with_timeout(timeout, lookup),
+            None => lookup(),
+        }
+    }
+
+    /// Fails, since plugins weren't compiled in.
+    #[cfg(not(feature = "plugins"))]
+    fn plugin(&self, path: &Path, _: &Subject) -> io::Result<Permissions> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "can't load plugin {}, since omst was built without the plugins feature",
+                path.display()
+            ),
+        ))
+    }
+
+    /// Determines a detailed report for the user, giving up after the timeout.
+    fn report(&self, subject: &Subject) -> io::Result<Report> {
+        #[allow(unused_mut)]
+        let mut result = match self.timeout {
+            Some(timeout) => {
+                let subject = subject.clone();
+                omst::with_timeout(timeout, move || subject.report())
+            }
+            None => subject.report(),
+        };
+        #[cfg(not(windows))]
+        if let (Some(false), Ok(report)) = (self.config.nobody_is_guest, &mut result) {
+            if let Some(range) = &report.range {
+                report.result = shadow::classify_by_range(report.uid, range);
+            }
+        }
+
+        // without what made them operators, reports describe ordinary users
+        if let (Some(false), Ok(report)) = (self.config.operator, &mut result) {
+            #[cfg(not(windows))]
+            {
+                report.admin_group = None;
+            }
+            #[cfg(windows)]
+            {
+                report.elevation_type = None;
+            }
+        }
+        result
+    }
+
+    /// Name to prefix errors with.
+    fn ident(&self) -> &'static str {
+        if self.be {
+            "omst-be"
+        } else {
+            "omst"
+        }
+    }
+
+    /// Sends an error to the system log, if requested.
+    fn log(&self, err: &io::Error) {
+        #[cfg(unix)]
+        if let Some(target) = self.log {
+            let ident = self.ident();
+
+            // we still print the error, so, failing to log it isn't worth reporting
+            let _ = log::log(target, ident, err);
+        }
+        #[cfg(not(unix))]
+        let _ = err;
+    }
+
+    /// User selected by the arguments.
+    fn subject(&self) -> Subject {
+        if let Some(host) = &self.host {
+            return Subject::Remote {
+                host: host.clone(),
+                user: self.query.clone(),
+            };
+        }
+        if let Some(name) = &self.query {
+            return Subject::User(name.clone());
+        }
+        #[cfg(not(windows))]
+        if let Some(uid) = self.uid {
+            return match &self.sysroot {
+                Some(root) => Subject::Sysroot {
+                    root: root.clone(),
+                    uid,
+                },
+                None => Subject::Uid(uid),
+            };
+        }
+        #[cfg(not(windows))]
+        if let Some(pid) = self.pid {
+            return Subject::Pid(pid);
+        }
+        Subject::Current
+    }
+}
+
+/// User whose permissions are being determined.
+#[derive(Clone)]
+enum Subject {
+    /// The current user.
+    Current,
+
+    /// A user given by name.
+    User(String),
+
+    /// A user given by ID.
+    #[cfg(not(windows))]
+    Uid(u32),
+
+    /// A user given by ID, classified for an alternate root.
+    #[cfg(not(windows))]
+    Sysroot {
+        /// Root containing `etc/login.defs`.
+        root: PathBuf,
+
+        /// ID of the user.
+        uid: u32,
+    },
+
+    /// The owner of a process.
+    #[cfg(not(windows))]
+    Pid(u32),
+
+    /// A user on another host.
+    Remote {
+        /// Host to connect to, possibly with a user.
+        host: String,
+
+        /// User to classify instead of the one we connect as.
+        user: Option<String>,
+    },
+}
+impl Subject {
+    /// Determines the permissions of the user.
+    fn omst(&self) -> io::Result<Permissions> {
+        match self {
+            Subject::Current => omst::omst(),
+            Subject::User(name) => omst::of_user(name),
+            #[cfg(not(windows))]
+            Subject::Uid(uid) => shadow::permissions_of(*uid).map_err(io::Error::from),
+            #[cfg(not(windows))]
+            Subject::Sysroot { root, uid } => shadow::of_uid_in(root, *uid)
+                .map(Permissions::from)
+                .map_err(io::Error::from),
+            #[cfg(not(windows))]
+            Subject::Pid(pid) => shadow::process_uid(*pid)
+                .and_then(shadow::permissions_of)
+                .map_err(io::Error::from),
+            Subject::Remote { host, user } => remote(host, user.as_deref()),
+        }
+    }
+
+    /// Determines a detailed report for the user.
+    fn report(&self) -> io::Result<Report> {
+        match self {
+            Subject::Current => omst::report(),
+            Subject::User(name) => omst::report_of_user(name),
+            #[cfg(not(windows))]
+            Subject::Uid(uid) => shadow::report_of(*uid).map_err(io::Error::from),
+            #[cfg(not(windows))]
+            Subject::Sysroot { root, uid } => {
+                shadow::report_of_in(root, *uid).map_err(io::Error::from)
+            }
+            #[cfg(not(windows))]
+            Subject::Pid(pid) => shadow::process_uid(*pid)
+                .and_then(shadow::report_of)
+                .map_err(io::Error::from),
+            Subject::Remote { .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "reports aren't available for other hosts",
+            )),
+        }
+    }
+
+    /// User ID, if the platform has one.
+    #[cfg(not(windows))]
+    fn uid(&self) -> Option<u32> {
+        match self {
+            Subject::Current => Some(shadow::euid()),
+            Subject::User(name) => shadow::user_id(name).ok(),
+            Subject::Uid(uid) | Subject::Sysroot { uid, .. } => Some(*uid),
+            Subject::Pid(pid) => shadow::process_uid(*pid).ok(),
+            Subject::Remote { .. } => None,
+        }
+    }
+
+    /// User ID, if the platform has one.
+    #[cfg(windows)]
+    fn uid(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Determines the permissions of a user on another host by running `omst-be` over SSH.
+fn remote(host: &str, user: Option<&str>) -> io::Result<Permissions> {
+    let mut ssh = proc::Command::new("ssh");
+    ssh.args(["-o", "BatchMode=yes", "--", host, "omst-be"]);
+    if let Some(user) = user {
+        // the remote shell parses the command again, so, it needs quoting
+        ssh.arg("--query")
+            .arg(format!("'{}'", user.replace('\'', "'\\''")));
+    }
+    let output = ssh.stdin(Stdio::null()).output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if output.status.success() {
+        if let Ok(level) = stdout.trim().parse() {
+            return Ok(level);
+        }
+    }
+
+    // older versions of `omst-be` print errors to stdout, but newer ones and ssh print to stderr
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = [stdout.trim(), stderr.trim()]
+        .into_iter()
+        .find(|message| !message.is_empty())
+        .unwrap_or("no output");
+    Err(io::Error::other(format!("{host}: {message}")))
+}
+
+/// String escaped for inclusion in JSON, including the quotes.
+struct JsonStr<T>(T);
+impl<T: fmt::Display> fmt::Display for JsonStr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for c in self.0.to_string().chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+/// Writes the result as a JSON object.
+fn write_json(
+    out: &mut impl Write,
+    args: &Args,
+    uid: Option<u32>,
+    result: &io::Result<Permissions>,
+) -> io::Result<()> {
+    let level = result.as_ref().ok().copied();
+    write!(out, "{{\"symbol\":{}", JsonStr(args.glyph(level)))?;
+    match result {
+        Ok(level) => write!(out, ",\"level\":{}", JsonStr(level))?,
+        Err(_) => write!(out, ",\"level\":null")?,
+    }
+    match uid {
+        Some(uid) => write!(out, ",\"uid\":{uid}")?,
+        None => write!(out, ",\"uid\":null")?,
+    }
+    write!(out, ",\"backend\":{}", JsonStr(omst::backend_name()))?;
+    match result {
+        Ok(_) => write!(out, ",\"error\":null}}{}", args.terminator()),
+        Err(err) => write!(out, ",\"error\":{}}}{}", JsonStr(err), args.terminator()),
+    }
+}
+
+/// String escaped for inclusion in a Prometheus label value, including the quotes.
+struct LabelStr<T>(T);
+impl<T: fmt::Display> fmt::Display for LabelStr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for c in self.0.to_string().chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+/// Writes the result as Prometheus metrics.
+fn write_metrics(
+    out: &mut impl Write,
+    args: &Args,
+    uid: Option<u32>,
+    result: &io::Result<Permissions>,
+) -> io::Result<()> {
+    let level = result.as_ref().ok().copied();
+    let name = level.map_or("unknown".to_owned(), |level| level.to_string());
+    writeln!(
+        out,
+        "# HELP omst_permission_level Permission level of the user, as labels."
+    )?;
+    writeln!(out, "# TYPE omst_permission_level gauge")?;
+    write!(
+        out,
+        "omst_permission_level{{symbol={},level={},backend={}",
+        LabelStr(args.glyph(level)),
+        LabelStr(name),
+        LabelStr(omst::backend_name())
+    )?;
+    if let Some(uid) = uid {
+        write!(out, ",uid=\"{uid}\"")?;
+    }
+    writeln!(out, "}} 1")?;
+    writeln!(
+        out,
+        "# HELP omst_error Whether an error occurred while determining the permission level."
+    )?;
+    writeln!(out, "# TYPE omst_error gauge")?;
+    writeln!(out, "omst_error {}", u8::from(result.is_err()))
+}
+
+/// Default powerline foreground and background colors for a level, or an error.
+fn powerline_colors(level: Option<Permissions>) -> (u8, u8) {
+    match level {
+        Some(Permissions::Guest) => (15, 30),
+        Some(Permissions::User) => (15, 28),
+        Some(Permissions::Operator) => (15, 25),
+        Some(Permissions::System) => (0, 178),
+        Some(Permissions::Absolute) => (15, 160),
+        None => (15, 90),
+    }
+}
+
+/// Writes the result as a powerline-go segment.
+fn write_powerline(
+    out: &mut impl Write,
+    args: &Args,
+    result: &io::Result<Permissions>,
+) -> io::Result<()> {
+    let level = result.as_ref().ok().copied();
+    let colors = &args.config.powerline;
+    let (fg, bg) = powerline_colors(level);
+    let fg = colors.foreground.get(level).copied().unwrap_or(fg);
+    let bg = colors.background.get(level).copied().unwrap_or(bg);
+    write!(
+        out,
+        "[{{\"name\":\"omst\",\"content\":{},\"foreground\":{fg},\"background\":{bg}}}]{}",
+        JsonStr(args.glyph(level)),
+        args.terminator()
+    )
+}
+
+/// tmux style for a level, or an error.
+fn tmux_style(level: Option<Permissions>) -> &'static str {
+    match level {
+        Some(Permissions::Guest) => "fg=cyan",
+        Some(Permissions::User) => "fg=green",
+        Some(Permissions::Operator) => "fg=blue",
+        Some(Permissions::System) => "fg=yellow",
+        Some(Permissions::Absolute) => "fg=red,bold",
+        None => "fg=magenta",
+    }
+}
+
+/// Writes the symbol wrapped in tmux style directives.
+fn write_tmux(out: &mut impl Write, args: &Args, level: Option<Permissions>) -> io::Result<()> {
+    // `#` starts a directive in the status line, so, it has to be doubled
+    let glyph = args.glyph(level).replace('#', "##");
+    write!(
+        out,
+        "#[{}]{glyph}#[default]{}",
+        tmux_style(level),
+        args.terminator()
+    )
+}
+
+/// Writes the result as shell variable assignments, which are empty if not applicable.
+///
+/// Errors are only printed for `omst-be`, to standard error, so that the output can always be
+/// evaluated.
+fn write_env(
+    out: &mut impl Write,
+    args: &Args,
+    uid: Option<u32>,
+    result: &io::Result<Permissions>,
+) -> io::Result<()> {
+    let level = result.as_ref().ok().copied();
+    if let (Err(err), true) = (result, args.be) {
+        write_error(args, err)?;
+    }
+    write!(
+        out,
+        "OMST_SYMBOL={} OMST_LEVEL={} OMST_UID={}{}",
+        init::quote(&args.glyph(level)),
+        level.map_or("unknown".to_owned(), |level| level.to_string()),
+        uid.map_or(String::new(), |uid| uid.to_string()),
+        args.terminator()
+    )
+}
+
+/// ANSI color code for a level, or an error.
+fn ansi_code(level: Option<Permissions>) -> &'static str {
+    match level {
+        Some(Permissions::Guest) => "36",
+        Some(Permissions::User) => "32",
+        Some(Permissions::Operator) => "34",
+        Some(Permissions::System) => "33",
+        Some(Permissions::Absolute) => "1;31",
+        None => "35",
+    }
+}
+
+/// Text tinted and escaped according to the arguments.
+fn tint(args: &Args, level: Option<Permissions>, text: impl fmt::Display) -> String {
+    let mut text = text.to_string();
+    if let Some(PromptShell::Zsh) = args.prompt {
+        text = text.replace('%', "%%");
+    }
+    if args.use_color() {
+        let (start, end) = match args.prompt {
+            Some(PromptShell::Bash) => ("\x01", "\x02"),
+            Some(PromptShell::Zsh) => ("%{", "%}"),
+            Some(PromptShell::Fish) | None => ("", ""),
+        };
+        let code = args
+            .config
+            .colors
+            .get(level)
+            .map_or(ansi_code(level), String::as_str);
+        format!("{start}\x1b[{code}m{end}{text}{start}\x1b[0m{end}")
+    } else {
+        text
+    }
+}
+
+/// Writes a line of output, tinted and escaped according to the arguments.
+fn write_tinted(
+    out: &mut impl Write,
+    args: &Args,
+    level: Option<Permissions>,
+    text: impl fmt::Display,
+) -> io::Result<()> {
+    out.write_all(tint(args, level, text).as_bytes())?;
+    out.write_all(args.terminator().as_bytes())
+}
+
+/// Message for an error, translated to the user's language if possible.
+fn message(err: &(dyn StdError + 'static)) -> String {
+    #[cfg(feature = "i18n")]
+    if let Some(message) = i18n::catalog().and_then(|catalog| catalog.error(err)) {
+        return message;
+    }
+    err.to_string()
+}
+
+/// Single-line trace of a report, translated to the user's language if possible.
+fn explain(report: &Report) -> String {
+    #[cfg(feature = "i18n")]
+    if let Some(trace) = i18n::catalog().and_then(|catalog| catalog.explain(report)) {
+        return trace;
+    }
+    report.explain().to_string()
+}
+
+/// Prints an error to standard error, followed by each of its sources.
+fn write_error(args: &Args, err: &(dyn StdError + 'static)) -> io::Result<()> {
+    let mut out = io::stderr().lock();
+    let mut message = message(err);
+    writeln!(out, "{}: {message}", args.ident())?;
+    let mut source = err.source();
+    while let Some(err) = source {
+        // most errors already include their source in their message, which we don't repeat
+        let next = err.to_string();
+        if !message.contains(&next) {
+            writeln!(out, "  caused by: {next}")?;
+        }
+        message = next;
+        source = err.source();
+    }
+    Ok(())
+}
+
+/// Writes a table of all accounts, returning whether it was successful.
+fn write_all(out: &mut impl Write, args: &Args) -> io::Result<bool> {
+    let term = args.terminator();
+    let mut accounts = match omst::accounts() {
+        Ok(accounts) => accounts,
+        Err(err) if args.be => return write_error(args, &err).map(|()| false),
+        Err(_) => return write_tinted(out, args, None, args.glyph(None)).map(|()| false),
+    };
+
+    // the accounts are classified without the configuration, so, it's applied here
+    #[cfg(not(windows))]
+    if args.config.nobody_is_guest == Some(false) {
+        // if the range can't be loaded, the results were guesses, which are kept
+        if let Ok(range) = shadow::uid_range() {
+            for account in &mut accounts {
+                if let Some(uid) = account.uid.filter(|uid| shadow::NOBODY.contains(uid)) {
+                    account.permissions = Ok(args.classify(uid, &range));
+                }
+            }
+        }
+    }
+    for account in &mut accounts {
+        if let (Some(false), Ok(level)) = (args.config.operator, &mut account.permissions) {
+            *level = level.flatten();
+        }
+    }
+
+    if let Some(table) = args.output {
+        table.write_row(out, &["name", "uid", "level", "symbol", "degraded"], term)?;
+        for account in &accounts {
+            let level = account.permissions.as_ref().ok().copied();
+            let uid = account.uid.map_or(String::new(), |uid| uid.to_string());
+            let name = match &account.permissions {
+                Ok(level) => level.to_string(),
+                Err(err) if args.be => message(err),
+                Err(_) => "unknown".to_owned(),
+            };
+            let degraded = if account.degraded { "true" } else { "false" };
+            table.write_row(
+                out,
+                &[&account.name, &uid, &name, &args.glyph(level), degraded],
+                term,
+            )?;
+        }
+        return Ok(accounts.iter().all(|account| account.permissions.is_ok()));
+    }
+
+    let width = accounts
+        .iter()
+        .map(|account| account.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for account in &accounts {
+        let level = account.permissions.as_ref().ok().copied();
+        write!(out, "{} ", tint(args, level, args.glyph(level)))?;
+        if let Some(uid) = account.uid {
+            write!(out, "{uid:>10} ")?;
+        }
+        write!(out, "{:<width$} ", account.name)?;
+        match &account.permissions {
+            Ok(level) if account.degraded => write!(out, "{level} (guessed){term}")?,
+            Ok(level) => write!(out, "{level}{term}")?,
+            Err(err) if args.be => write!(out, "{}{term}", message(err))?,
+            Err(_) => write!(out, "unknown{term}")?,
+        }
+    }
+    Ok(accounts.iter().all(|account| account.permissions.is_ok()))
+}
+
+/// Writes a table of groups, their names, and their kinds, noting which ones allow elevating.
+#[cfg(not(windows))]
+fn write_groups(out: &mut impl Write, groups: &[shadow::Group]) -> io::Result<()> {
+    let width = groups
+        .iter()
+        .map(|group| group.name.as_deref().map_or(1, |name| name.chars().count()))
+        .max()
+        .unwrap_or(0);
+    for group in groups {
+        let name = group.name.as_deref().unwrap_or("-");
+        write!(out, "{:>10} {name:<width$} {}", group.gid, group.kind)?;
+        if shadow::ADMIN_GROUPS.contains(&name) {
+            write!(out, ", can elevate")?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Output collected on the stack, and then written to standard output all at once.
+///
+/// Since `omst` usually runs once per prompt, this lets the common case issue a single `write`
+/// without allocating or locking standard output. Larger outputs spill over onto the heap.
+struct Buffer {
+    /// Output which fits inline.
+    inline: [u8; 128],
+
+    /// Number of bytes used in `inline`.
+    len: usize,
+
+    /// All of the output, once it no longer fits inline.
+    spill: Vec<u8>,
+}
+impl Buffer {
+    /// Creates an empty buffer.
+    fn new() -> Buffer {
+        Buffer {
+            inline: [0; 128],
+            len: 0,
+            spill: Vec::new(),
+        }
+    }
+
+    /// Contents of the buffer.
+    fn as_bytes(&self) -> &[u8] {
+        if self.spill.is_empty() {
+            &self.inline[..self.len]
+        } else {
+            &self.spill
+        }
+    }
+
+    /// Writes the contents of the buffer to standard output.
+    fn finish(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            // standard output is owned by the process, so, we must not close it
+            let mut stdout = mem::ManuallyDrop::new(unsafe { fs::File::from_raw_fd(1) });
+            match stdout.write_all(self.as_bytes()) {
+                // like `io::stdout`, silently ignore standard output being closed
+                Err(err) if err.raw_os_error() == Some(libc::EBADF) => Ok(()),
+                result => result,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            io::stdout().write_all(self.as_bytes())
+        }
+    }
+}
+impl Write for Buffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.spill.is_empty() && data.len() <= self.inline.len() - self.len {
+            self.inline[self.len..][..data.len()].copy_from_slice(data);
+            self.len += data.len();
+        } else {
+            if self.spill.is_empty() {
+                self.spill.extend_from_slice(&self.inline[..self.len]);
+            }
+            self.spill.extend_from_slice(data);
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes the result according to the arguments, returning the exit status.
+fn write_result(out: &mut impl Write, args: &Args, subject: &Subject) -> io::Result<ExitCode> {
+    let format = args.format();
+    let term = args.terminator();
+    if let (None, Format::Verbose | Format::Explain) = (&args.template, format) {
+        return match args.report(subject) {
+            Ok(report) if format == Format::Explain => {
+                write!(out, "{}{term}", explain(&report)).map(|()| ExitCode::SUCCESS)
+            }
+            Ok(report) => write!(out, "{report}{term}").map(|()| ExitCode::SUCCESS),
+            Err(err) => {
+                args.log(&err);
+                let status = ExitCode::from(error_status(&err));
+                if args.strict {
+                    Ok(status)
+                } else if args.be {
+                    write_error(args, &err).map(|()| status)
+                } else {
+                    write!(out, "{}{term}", message(&err)).map(|()| status)
+                }
+            }
+        };
+    }
+
+    let result = args.omst(subject);
+    let status = match &result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            args.log(err);
+            ExitCode::from(error_status(err))
+        }
+    };
+    write_level(out, args, || subject.uid(), result)?;
+    Ok(status)
+}
+
+/// Writes a single level according to the arguments.
+///
+/// The user ID is only computed if it's needed for the output.
+fn write_level(
+    out: &mut impl Write,
+    args: &Args,
+    uid: impl FnOnce() -> Option<u32>,
+    result: io::Result<Permissions>,
+) -> io::Result<()> {
+    if args.strict && result.is_err() {
+        return Ok(());
+    }
+    if let Some(template) = &args.template {
+        let level = result.as_ref().ok().copied();
+        let uid = if template.has(Field::Uid) {
+            uid()
+        } else {
+            None
+        };
+        let text = template.render(|field| match field {
+            Field::Symbol => args.glyph(level),
+            Field::Name => level.map_or("unknown".to_owned(), |level| level.to_string()),
+            Field::Uid => match uid {
+                Some(uid) => uid.to_string(),
+                None => String::new(),
+            },
+            Field::Backend => omst::backend_name().to_owned(),
+            Field::Error => match &result {
+                Ok(_) => String::new(),
+                Err(err) => err.to_string(),
+            },
+        });
+        return write_tinted(out, args, level, text);
+    }
+    match (args.format(), result) {
+        (Format::Json, result) => write_json(out, args, uid(), &result),
+        (Format::Powerline, result) => write_powerline(out, args, &result),
+        (Format::Metrics, result) => write_metrics(out, args, uid(), &result),
+        (Format::Tmux, result) => write_tmux(out, args, result.ok()),
+        (Format::Env, result) => write_env(out, args, uid(), &result),
+        (Format::Name, Ok(level)) => write_tinted(out, args, Some(level), level),
+        (_, Ok(level)) => write_tinted(out, args, Some(level), args.glyph(Some(level))),
+        (_, Err(_)) if args.fallback.is_some() => write_tinted(out, args, None, args.glyph(None)),
+        (_, Err(err)) if args.be => write_error(args, &err),
+        (Format::Name, Err(_)) => write_tinted(out, args, None, "unknown"),
+        (_, Err(_)) => write_tinted(out, args, None, args.glyph(None)),
+    }
+}
+
+/// User who invoked us: the user who ran `sudo` if we're root under it, or the real user.
+#[cfg(not(windows))]
+fn invoking_uid() -> u32 {
+    let uid = unsafe { libc::getuid() };
+    if uid == 0 {
+        if let Some(sudo) = env::var("SUDO_UID").ok().and_then(|uid| uid.parse().ok()) {
+            return sudo;
+        }
+    }
+    uid
+}
+
+/// Writes the symbols for the invoking and effective users, returning whether both were
+/// successful.
+#[cfg(not(windows))]
+fn write_both(out: &mut impl Write, args: &Args) -> io::Result<bool> {
+    let real = args.omst(&Subject::Uid(invoking_uid()));
+    let effective = args.omst(&Subject::Current);
+    let is_ok = real.is_ok() && effective.is_ok();
+    for result in [real, effective] {
+        if let Err(err) = &result {
+            args.log(err);
+        }
+        let level = result.ok();
+        write!(out, "{}", tint(args, level, args.glyph(level)))?;
+    }
+    write!(out, "{}", args.terminator())?;
+    Ok(is_ok)
+}
+
+/// Classifies users read from standard input, returning whether all were successful.
+///
+/// Each line contains either a user name or, on unix-family systems, a user ID. Empty lines are
+/// ignored.
+fn write_stdin(out: &mut impl Write, args: &Args) -> io::Result<bool> {
+    #[cfg(not(windows))]
+    let mut range = None;
+    let mut all_ok = true;
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        #[cfg(not(windows))]
+        let (uid, result) = match input.parse().or_else(|_| shadow::user_id(input)) {
+            Err(err) => (None, Err(io::Error::from(err))),
+            Ok(uid) => {
+                // only read login.defs once, unless it fails
+                if range.is_none() {
+                    range = shadow::uid_range().ok();
+                }
+                let result = match &range {
+                    Some(range) => Ok(args.classify(uid, range)),
+                    None => args.lookup(&Subject::Uid(uid)),
+                };
+                (Some(uid), args.flatten(result))
+            }
+        };
+        #[cfg(windows)]
+        let (uid, result) = (None, args.flatten(omst::of_user(input)));
+
+        all_ok &= result.is_ok();
+        write_level(out, args, || uid, result)?;
+    }
+    Ok(all_ok)
+}
+
+/// Reprints the result whenever it changes, checking every `interval`.
+fn watch(args: &Args, subject: &Subject, interval: Duration) -> io::Result<ExitCode> {
+    let mut wait = Wait::new(subject, interval);
+    let mut out = io::stdout().lock();
+    let mut last = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        write_result(&mut buf, args, subject)?;
+        if buf != last {
+            out.write_all(&buf)?;
+            out.flush()?;
+            mem::swap(&mut buf, &mut last);
+        }
+        wait.wait()?;
+    }
+}
+
+/// Strategy for waiting until the result might have changed.
+enum Wait {
+    /// Check again after a fixed interval.
+    Interval(Duration),
+
+    /// Check again when the inputs change.
+    #[cfg(target_os = "linux")]
+    Notify(Watcher),
+}
+impl Wait {
+    /// Picks a strategy for the subject, falling back to `interval` if changes can't be watched.
+    fn new(subject: &Subject, interval: Duration) -> Wait {
+        #[cfg(target_os = "linux")]
+        {
+            // the owner of a process can change at any time, and remote files can't be watched
+            let dir = match subject {
+                Subject::Current | Subject::User(_) | Subject::Uid(_) => {
+                    Some(PathBuf::from("/etc"))
+                }
+                Subject::Sysroot { root, .. } => Some(root.join("etc")),
+                Subject::Pid(_) | Subject::Remote { .. } => None,
+            };
+            if let Some(watcher) = dir.and_then(|dir| Watcher::new(&dir).ok()) {
+                return Wait::Notify(watcher);
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = subject;
+        Wait::Interval(interval)
+    }
+
+    /// Blocks until the result might have changed.
+    fn wait(&mut self) -> io::Result<()> {
+        match self {
+            Wait::Interval(interval) => thread::sleep(*interval),
+            #[cfg(target_os = "linux")]
+            Wait::Notify(watcher) => while !watcher.wait(None)? {},
+        }
+        Ok(())
+    }
+
+    /// Whether the result might have changed since it was computed at `updated`.
+    #[cfg(unix)]
+    fn stale(&mut self, updated: Instant) -> io::Result<bool> {
+        match self {
+            Wait::Interval(interval) => Ok(updated.elapsed() >= *interval),
+            #[cfg(target_os = "linux")]
+            Wait::Notify(watcher) => watcher.wait(Some(Duration::ZERO)),
+        }
+    }
+}
+
+/// Limit on the size of an HTTP request to `--serve`, including its headers.
+const REQUEST_MAX: u64 = 8192;
+
+/// Number of connections to `--serve` which are handled at once.
+const SERVE_WORKERS: usize = 16;
+
+/// Time a client of `--serve` has to send its request and receive the response.
+const SERVE_DEADLINE: Duration = Duration::from_secs(5);
+
+/// Responds to each HTTP request made to `addr`.
+///
+/// Connections are handled by a fixed pool of [`SERVE_WORKERS`] threads, so that a slow client
+/// can't hold up the others, and many clients can't exhaust the threads of the system.
+fn serve(args: &Args, subject: &Subject, addr: SocketAddr) -> io::Result<ExitCode> {
+    let listener = TcpListener::bind(addr)?;
+    thread::scope(|scope| {
+        for _ in 0..SERVE_WORKERS {
+            scope.spawn(|| loop {
+                let Ok((stream, _)) = listener.accept() else {
+                    continue;
+                };
+
+                // clients hanging up early isn't our problem
+                let _ = respond(stream, args, subject);
+            });
+        }
+    });
+    Ok(ExitCode::SUCCESS)
+}
+
+/// A [`TcpStream`] which fails to read or write after a deadline.
+///
+/// Timeouts on the stream itself only limit each read or write, which a client can get around by
+/// sending its request a byte at a time.
+#[derive(Clone, Copy)]
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+impl<'a> Deadline<'a> {
+    /// Times out the stream after the given duration from now.
+    fn new(stream: &'a TcpStream, timeout: Duration) -> Deadline<'a> {
+        Deadline {
+            stream,
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    /// Time left before the deadline, or an error if there's none.
+    fn remaining(&self) -> io::Result<Duration> {
+        match self.deadline.checked_duration_since(Instant::now()) {
+            Some(remaining) if !remaining.is_zero() => Ok(remaining),
+            _ => Err(io::ErrorKind::TimedOut.into()),
+        }
+    }
+}
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.set_read_timeout(Some(self.remaining()?))?;
+        self.stream.read(buf)
+    }
+}
+impl Write for Deadline<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.set_write_timeout(Some(self.remaining()?))?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Reads an HTTP request from `stream` and writes the response.
+///
+/// Requests longer than [`REQUEST_MAX`] are rejected without reading the rest of them, and the
+/// whole exchange has to finish within [`SERVE_DEADLINE`].
+fn respond(stream: TcpStream, args: &Args, subject: &Subject) -> io::Result<()> {
+    let mut stream = Deadline::new(&stream, SERVE_DEADLINE);
+    let mut reader = BufReader::new(stream).take(REQUEST_MAX);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+
+    // the headers don't matter, but closing the connection without reading them can drop the
+    // response on some systems
+    let mut header = String::new();
+    let mut complete = request.ends_with('\n');
+    while complete {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 || !header.ends_with('\n') {
+            complete = false;
+        } else if header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    let mut body = Vec::new();
+    let (status, content_type) = match (method, path) {
+        _ if !complete => {
+            writeln!(body, "bad request")?;
+            ("400 Bad Request", "text/plain; charset=utf-8")
+        }
+        ("GET" | "HEAD", "/" | "/json") => {
+            let result = args.omst(subject);
+            if path == "/json" {
+                write_json(&mut body, args, subject.uid(), &result)?;
+            } else {
+                writeln!(body, "{}", args.glyph(result.as_ref().ok().copied()))?;
+            }
+            let status = match result {
+                Ok(_) => "200 OK",
+                Err(_) => "500 Internal Server Error",
+            };
+            let content_type = match path {
+                "/json" => "application/json",
+                _ => "text/plain; charset=utf-8",
+            };
+            (status, content_type)
+        }
+        ("GET" | "HEAD", _) => {
+            writeln!(body, "not found")?;
+            ("404 Not Found", "text/plain; charset=utf-8")
+        }
+        _ => {
+            writeln!(body, "method not allowed")?;
+            ("405 Method Not Allowed", "text/plain; charset=utf-8")
+        }
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&body)?;
+    }
+    Ok(())
+}
+
+/// How long the daemon reuses a result before recomputing it, if changes can't be watched.
+#[cfg(unix)]
+const DAEMON_TTL: Duration = Duration::from_secs(1);
+
+/// Writes the cached result to each client which connects to the socket at `path`.
+#[cfg(unix)]
+fn daemon(args: &Args, subject: &Subject, path: &Path) -> io::Result<ExitCode> {
+    let listener = match UnixListener::bind(path) {
+        Err(err)
+            if err.kind() == io::ErrorKind::AddrInUse && UnixStream::connect(path).is_err() =>
+        {
+            // a previous daemon exited without cleaning up, unless it isn't a socket at all
+            if !fs::symlink_metadata(path)?.file_type().is_socket() {
+                return Err(err);
+            }
+            fs::remove_file(path)?;
+            UnixListener::bind(path)?
+        }
+        result => result?,
+    };
+    let mut wait = Wait::new(subject, DAEMON_TTL);
+    let mut buf = Vec::new();
+    let mut updated: Option<Instant> = None;
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        let stale = match updated {
+            Some(updated) => wait.stale(updated)?,
+            None => true,
+        };
+        if stale {
+            buf.clear();
+            write_result(&mut buf, args, subject)?;
+            updated = Some(Instant::now());
+        }
+
+        // clients hanging up early isn't our problem
+        let _ = stream.write_all(&buf);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Exit status used for a level with `--quiet`, which increases with the level.
+fn quiet_status(level: Permissions) -> u8 {
+    match level {
+        Permissions::Guest => 20,
+        Permissions::User => 21,
+        Permissions::Operator => 22,
+        Permissions::System => 23,
+        Permissions::Absolute => 24,
+    }
+}
+
+/// Exit status used for an error which prevented the level from being determined.
+///
+/// | Status | Meaning                                                 |
+/// |--------|---------------------------------------------------------|
+/// | 1      | any other error, like failing to reach a remote host    |
+/// | 10     | a data source (like `/etc/login.defs`) is missing       |
+/// | 11     | a data source couldn't be parsed or had invalid values  |
+/// | 12     | an OS API failed, or a data source couldn't be read     |
+/// | 13     | the user does not exist locally                         |
+/// | 14     | the lookup timed out                                    |
+fn error_status(err: &io::Error) -> u8 {
+    #[cfg(not(windows))]
+    if let Some(err) = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<shadow::Error>())
+    {
+        return match err.code() {
+            100 => 10,
+            103 | 104 | 110..=113 => 11,
+            121 | 130 => 13,
+            _ => 12,
+        };
+    }
+    match err.kind() {
+        io::ErrorKind::TimedOut => 14,
+        io::ErrorKind::InvalidData => 11,
+        _ if err.raw_os_error().is_some() => 12,
+        _ if err
+            .get_ref()
+            .and_then(|err| err.downcast_ref::<io::Error>())
+            .is_some_and(|err| err.raw_os_error().is_some()) =>
+        {
+            12
+        }
+        _ => 1,
+    }
+}
+
+/// Parses a level by name or symbol, listing each level's description in `--help`.
+fn level_parser() -> impl TypedValueParser<Value = Permissions> {
+    const LEVELS: [(&str, &str, Permissions); 5] = [
+        ("guest", "%", Permissions::Guest),
+        ("user", "$", Permissions::User),
+        ("operator", "&", Permissions::Operator),
+        ("system", "@", Permissions::System),
+        ("absolute", "#", Permissions::Absolute),
+    ];
+    PossibleValuesParser::new(LEVELS.map(|(name, symbol, level)| {
+        PossibleValue::new(name)
+            .alias(symbol)
+            .help(level.description())
+    }))
+    // every possible value is a level's name or symbol
+    .map(|level| level.parse::<Permissions>().unwrap())
+}
+
+/// Parses a timeout in milliseconds.
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    s.parse()
+        .map(Duration::from_millis)
+        .map_err(|err| err.to_string())
+}
+
+/// Parses a record separator, which is either the name of a character or a literal string.
+fn parse_separator(s: &str) -> Result<String, String> {
+    Ok(match s {
+        "newline" => "\n",
+        "tab" => "\t",
+        "nul" => "\0",
+        s => s,
+    }
+    .to_owned())
+}
+
+/// Parses an interval in (possibly fractional) seconds.
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let secs = s.parse::<f64>().map_err(|err| err.to_string())?;
+    Duration::try_from_secs_f64(secs).map_err(|err| err.to_string())
+}
+
+/// Runs the binary; `be` indicates that errors should be fully printed.
+pub fn main(be: bool) -> io::Result<ExitCode> {
+    // building the parser takes longer than everything else, so, skip it if there's nothing to parse
+    let mut args = if env::args_os().len() > 1 {
+        Args::parse()
+    } else {
+        Args::default()
+    };
+    args.be = be;
+    let name = if be { "omst-be" } else { "omst" };
+    match args.command.take() {
+        None => {}
+        Some(Command::Be) => args.be = true,
+        Some(Command::Who { user }) => {
+            args.verbose = true;
+            args.query = user;
+        }
+        Some(Command::Check { level, user }) => {
+            args.check = Some(level);
+            args.query = user;
+        }
+        Some(Command::Explain { user }) => {
+            args.explain = true;
+            args.query = user;
+        }
+        Some(Command::Query { user }) => args.query = Some(user),
+        Some(Command::Tmux { user }) => {
+            args.tmux = true;
+            args.query = user;
+        }
+        Some(Command::Env { user }) => {
+            args.env = true;
+            args.query = user;
+        }
+        #[cfg(windows)]
+        Some(Command::Elevation) => {
+            return match omst::winapi::elevation() {
+                Ok(elevation) => {
+                    writeln!(io::stdout(), "{elevation}")?;
+                    Ok(ExitCode::SUCCESS)
+                }
+                Err(err) => {
+                    write_error(&args, &err)?;
+                    Ok(ExitCode::FAILURE)
+                }
+            };
+        }
+        #[cfg(not(windows))]
+        Some(Command::Groups) => {
+            return match shadow::groups() {
+                Ok(groups) => {
+                    write_groups(&mut io::stdout().lock(), &groups)?;
+                    Ok(ExitCode::SUCCESS)
+                }
+                Err(err) => {
+                    write_error(&args, &err)?;
+                    Ok(ExitCode::FAILURE)
+                }
+            };
+        }
+        Some(Command::Doctor) => {
+            return Ok(if doctor::run(&mut io::stdout().lock())? {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            });
+        }
+        Some(Command::Init { shell }) => {
+            let exe = env::current_exe()?;
+            let Some(exe) = exe.to_str() else {
+                eprintln!("{name}: path to {name} is not valid UTF-8");
+                return Ok(ExitCode::from(2));
+            };
+            io::stdout().write_all(init::script(shell, exe).as_bytes())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Completions { shell }) => {
+            // generating directly to stdout panics on errors, so, we buffer instead
+            let mut buf = Vec::new();
+            clap_complete::generate(shell, &mut Args::command(), name, &mut buf);
+            io::stdout().write_all(&buf)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
+
+    if let Some(field) = args.field {
+        args.template = Some(Template::from(field));
+    }
+
+    args.config = match config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{name}: {err}");
+            return Ok(ExitCode::from(2));
+        }
+    };
+
+    if args.all || args.stdin {
+        let mut out = io::stdout().lock();
+        let is_ok = if args.all {
+            write_all(&mut out, &args)?
+        } else {
+            write_stdin(&mut out, &args)?
+        };
+        return Ok(if is_ok {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    #[cfg(not(windows))]
+    if args.both {
+        let is_ok = write_both(&mut io::stdout().lock(), &args)?;
+        return Ok(if is_ok {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    #[cfg(not(windows))]
+    if (args.container || args.config.container_suffix.is_some()) && shadow::container().is_some() {
+        args.suffix = Some(
+            args.config
+                .container_suffix
+                .clone()
+                .unwrap_or_else(|| "'".to_owned()),
+        );
+    }
+
+    let subject = args.subject();
+    #[cfg(not(windows))]
+    if (args.switched || args.config.switched_suffix.is_some())
+        && matches!(subject, Subject::Current)
+        && shadow::tty_owner().is_some_and(|owner| owner != shadow::euid())
+    {
+        let suffix = args.config.switched_suffix.as_deref().unwrap_or("^");
+        args.suffix.get_or_insert_with(String::new).push_str(suffix);
+    }
+    if args.quiet {
+        return match args.omst(&subject) {
+            Ok(level) => Ok(ExitCode::from(quiet_status(level))),
+            Err(err) => {
+                args.log(&err);
+                Ok(ExitCode::from(error_status(&err)))
+            }
+        };
+    }
+    if let Some(min) = args.check {
+        return match args.omst(&subject) {
+            Ok(level) if level >= min => Ok(ExitCode::SUCCESS),
+            Ok(_) => Ok(ExitCode::FAILURE),
+            Err(err) => {
+                if be && !args.strict {
+                    write_error(&args, &err)?;
+                }
+                Ok(ExitCode::from(error_status(&err)))
+            }
+        };
+    }
+    if let Some(addr) = args.serve {
+        return serve(&args, &subject, addr);
+    }
+    #[cfg(unix)]
+    if let Some(path) = &args.daemon {
+        let Some(path) = path.clone().or_else(|| {
+            env::var_os("XDG_RUNTIME_DIR")
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| PathBuf::from(dir).join("omst.sock"))
+        }) else {
+            eprintln!("{name}: no socket given for --daemon, and XDG_RUNTIME_DIR is unset");
+            return Ok(ExitCode::from(2));
+        };
+        return daemon(&args, &subject, &path);
+    }
+    #[cfg(all(not(windows), feature = "dbus"))]
+    if args.dbus {
+        let mut wait = Wait::new(&subject, args.watch.unwrap_or(Duration::from_secs(1)));
+        let result = crate::dbus::serve(
+            || wait.wait(),
+            || {
+                let level = args.omst(&subject).ok();
+                let name = level.map_or("unknown".to_owned(), |level| level.to_string());
+                (args.glyph(level), name)
+            },
+        );
+        if let Err(err) = result {
+            eprintln!("{name}: {err}");
+        }
+        return Ok(ExitCode::FAILURE);
+    }
+    if let Some(interval) = args.watch {
+        return watch(&args, &subject, interval);
+    }
+
+    let mut out = Buffer::new();
+    let status = write_result(&mut out, &args, &subject)?;
+    out.finish()?;
+    Ok(status)
+}
+
+#[test]
+fn table_rows() {
+    let row = |format: TableFormat, row: &[&str], term| {
+        let mut out = Vec::new();
+        format.write_row(&mut out, row, term).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let header = ["name", "uid", "level", "symbol", "degraded"];
+    assert_eq!(
+        row(TableFormat::Csv, &header, "\n"),
+        "name,uid,level,symbol,degraded\n"
+    );
+    assert_eq!(
+        row(TableFormat::Tsv, &header, "\n"),
+        "name\tuid\tlevel\tsymbol\tdegraded\n"
+    );
+
+    assert_eq!(
+        row(
+            TableFormat::Csv,
+            &["a,b", "say \"hi\"", "two\nlines", "cr\r", "plain"],
+            "\n"
+        ),
+        "\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\"cr\r\",plain\n"
+    );
+    assert_eq!(row(TableFormat::Csv, &["", "x"], "\0"), ",x\0");
+
+    assert_eq!(
+        row(
+            TableFormat::Tsv,
+            &["a\tb", "back\\slash", "two\nlines", "cr\r", "\"q\""],
+            "\n"
+        ),
+        "a\\tb\tback\\\\slash\ttwo\\nlines\tcr\\r\t\"q\"\n"
+    );
+    assert_eq!(row(TableFormat::Tsv, &["\\t"], "\n"), "\\\\t\n");
+}
+
+#[test]
+fn json_strings() {
+    assert_eq!(JsonStr("plain").to_string(), "\"plain\"");
+    assert_eq!(
+        JsonStr("say \"hi\" \\ two\nlines\r\ttab").to_string(),
+        "\"say \\\"hi\\\" \\\\ two\\nlines\\r\\ttab\""
+    );
+    assert_eq!(
+        JsonStr("\0\u{1b}\u{7f}\u{85}").to_string(),
+        "\"\\u0000\\u001b\\u007f\\u0085\""
+    );
+    assert_eq!(JsonStr("\u{1f642} é").to_string(), "\"\u{1f642} é\"");
+}
+
+#[test]
+fn tinted_prompts() {
+    let mut args = Args {
+        color: Some(ColorChoice::Always),
+        ..Args::default()
+    };
+    assert_eq!(
+        tint(&args, Some(Permissions::User), "$"),
+        "\x1b[32m$\x1b[0m"
+    );
+    assert_eq!(tint(&args, None, "?"), "\x1b[35m?\x1b[0m");
+
+    args.prompt = Some(PromptShell::Bash);
+    assert_eq!(
+        tint(&args, Some(Permissions::Absolute), "#"),
+        "\x01\x1b[1;31m\x02#\x01\x1b[0m\x02"
+    );
+
+    args.prompt = Some(PromptShell::Zsh);
+    assert_eq!(
+        tint(&args, Some(Permissions::Guest), "50%"),
+        "%{\x1b[36m%}50%%%{\x1b[0m%}"
+    );
+
+    args.prompt = Some(PromptShell::Fish);
+    assert_eq!(
+        tint(&args, Some(Permissions::Operator), "%"),
+        "\x1b[34m%\x1b[0m"
+    );
+
+    args.config.colors.system = Some("4;33".to_owned());
+    assert_eq!(
+        tint(&args, Some(Permissions::System), "@"),
+        "\x1b[4;33m@\x1b[0m"
+    );
+
+    // zsh still needs `%` escaped without colors
+    args.color = Some(ColorChoice::Never);
+    args.prompt = Some(PromptShell::Zsh);
+    assert_eq!(tint(&args, Some(Permissions::Guest), "50%"), "50%%");
+    args.prompt = Some(PromptShell::Bash);
+    assert_eq!(tint(&args, Some(Permissions::Guest), "50%"), "50%");
+}
+
+#[test]
+fn exit_statuses() {
+    assert_eq!(
+        [
+            Permissions::Guest,
+            Permissions::User,
+            Permissions::Operator,
+            Permissions::System,
+            Permissions::Absolute,
+        ]
+        .map(quiet_status),
+        [20, 21, 22, 23, 24]
+    );
+
+    assert_eq!(error_status(&io::Error::other("no route")), 1);
+    assert_eq!(error_status(&io::ErrorKind::InvalidData.into()), 11);
+    assert_eq!(error_status(&io::Error::from_raw_os_error(5)), 12);
+    assert_eq!(
+        error_status(&io::Error::other(io::Error::from_raw_os_error(5))),
+        12
+    );
+    assert_eq!(error_status(&io::ErrorKind::TimedOut.into()), 14);
+
+    #[cfg(not(windows))]
+    {
+        let status = |err: shadow::Error| error_status(&err.into());
+        let login_defs = |kind: io::ErrorKind| shadow::Error::LoginDefs {
+            operation: shadow::Operation::Open,
+            error: io::Error::from(kind).into(),
+            path: None,
+        };
+        assert_eq!(status(login_defs(io::ErrorKind::NotFound)), 10);
+        assert_eq!(status(login_defs(io::ErrorKind::PermissionDenied)), 12);
+        assert_eq!(
+            status(shadow::Error::InvalidDef {
+                def: shadow::Def::Min,
+                problem: shadow::Problem::Empty,
+                path: None,
+            }),
+            11
+        );
+        assert_eq!(
+            status(shadow::Error::LineTooLong {
+                line: 3,
+                path: None
+            }),
+            11
+        );
+        assert_eq!(
+            status(shadow::Error::UnknownUser {
+                name: "nobody-here".into(),
+            }),
+            13
+        );
+    }
+}
+
+#[test]
+fn separators() {
+    assert_eq!(parse_separator("newline").as_deref(), Ok("\n"));
+    assert_eq!(parse_separator("tab").as_deref(), Ok("\t"));
+    assert_eq!(parse_separator("nul").as_deref(), Ok("\0"));
+    assert_eq!(parse_separator(", ").as_deref(), Ok(", "));
+    assert_eq!(parse_separator("").as_deref(), Ok(""));
+
+    let terminator = |args: &[&str]| {
+        let args = Args::try_parse_from(["omst"].iter().chain(args)).unwrap();
+        args.terminator().to_owned()
+    };
+    assert_eq!(terminator(&[]), "\n");
+    assert_eq!(terminator(&["-n"]), "");
+    assert_eq!(terminator(&["-0"]), "\0");
+    assert_eq!(terminator(&["--all", "--separator", "tab"]), "\t");
+    assert_eq!(terminator(&["--stdin", "--separator=;"]), ";");
+
+    // separators only apply to records, and replace the other terminators
+    assert!(Args::try_parse_from(["omst", "--separator", "tab"]).is_err());
+    assert!(Args::try_parse_from(["omst", "--all", "--separator", "tab", "-0"]).is_err());
+}
+
+#[test]
+fn writers() {
+    let write = |f: &dyn Fn(&mut Vec<u8>) -> io::Result<()>| {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let backend = omst::backend_name();
+    let mut args = Args::default();
+    let user: io::Result<Permissions> = Ok(Permissions::User);
+    let failed: io::Result<Permissions> = Err(io::Error::other("no \"login.defs\""));
+
+    assert_eq!(
+        write(&|out| write_metrics(out, &args, Some(1000), &user)),
+        format!(
+            "# HELP omst_permission_level Permission level of the user, as labels.\n\
+             # TYPE omst_permission_level gauge\n\
+             omst_permission_level{{symbol=\"$\",level=\"user\",backend=\"{backend}\",\
+             uid=\"1000\"}} 1\n\
+             # HELP omst_error Whether an error occurred while determining the permission level.\n\
+             # TYPE omst_error gauge\n\
+             omst_error 0\n"
+        )
+    );
+    assert_eq!(
+        write(&|out| write_env(out, &args, Some(1000), &user)),
+        "OMST_SYMBOL='$' OMST_LEVEL=user OMST_UID=1000\n"
+    );
+    assert_eq!(
+        write(&|out| write_env(out, &args, None, &failed)),
+        "OMST_SYMBOL='?' OMST_LEVEL=unknown OMST_UID=\n"
+    );
+    assert_eq!(
+        write(&|out| write_tmux(out, &args, Some(Permissions::Absolute))),
+        "#[fg=red,bold]###[default]\n"
+    );
+    assert_eq!(
+        write(&|out| write_tmux(out, &args, None)),
+        "#[fg=magenta]?#[default]\n"
+    );
+    assert_eq!(
+        write(&|out| write_powerline(out, &args, &Ok(Permissions::Operator))),
+        "[{\"name\":\"omst\",\"content\":\"&\",\"foreground\":15,\"background\":25}]\n"
+    );
+    assert_eq!(
+        write(&|out| write_json(out, &args, None, &failed)),
+        format!(
+            "{{\"symbol\":\"?\",\"level\":null,\"uid\":null,\"backend\":\"{backend}\",\
+             \"error\":\"no \\\"login.defs\\\"\"}}\n"
+        )
+    );
+
+    // glyphs are escaped for each format
+    args.config.glyphs.unknown = Some("it's \"#\"\\\n".to_owned());
+    args.config.powerline.background.unknown = Some(52);
+    args.no_newline = true;
+    assert_eq!(
+        write(&|out| write_metrics(out, &args, None, &failed)),
+        format!(
+            "# HELP omst_permission_level Permission level of the user, as labels.\n\
+             # TYPE omst_permission_level gauge\n\
+             omst_permission_level{{symbol=\"it's \\\"#\\\"\\\\\\n\",level=\"unknown\",\
+             backend=\"{backend}\"}} 1\n\
+             # HELP omst_error Whether an error occurred while determining the permission level.\n\
+             # TYPE omst_error gauge\n\
+             omst_error 1\n"
+        )
+    );
+    assert_eq!(
+        write(&|out| write_env(out, &args, None, &failed)),
+        "OMST_SYMBOL='it'\\''s \"#\"\\\n' OMST_LEVEL=unknown OMST_UID="
+    );
+    assert_eq!(
+        write(&|out| write_tmux(out, &args, None)),
+        "#[fg=magenta]it's \"##\"\\\n#[default]"
+    );
+    assert_eq!(
+        write(&|out| write_powerline(out, &args, &failed)),
+        "[{\"name\":\"omst\",\"content\":\"it's \\\"#\\\"\\\\\\n\",\"foreground\":15,\
+         \"background\":52}]"
+    );
+}
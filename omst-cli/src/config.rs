@@ -0,0 +1,377 @@
+//! User configuration file shared by `omst` and `omst-be`.
+use clap::ValueEnum;
+use omst::Permissions;
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+/// How the result is printed.
+#[derive(Deserialize, ValueEnum, Copy, Clone, Eq, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// The symbol for the level.
+    Symbol,
+
+    /// The name of the level.
+    Name,
+
+    /// A JSON object.
+    Json,
+
+    /// A detailed report.
+    Verbose,
+
+    /// A single-line trace.
+    Explain,
+
+    /// A powerline-go segment.
+    Powerline,
+
+    /// The symbol with tmux style directives.
+    Tmux,
+
+    /// Prometheus metrics in the text exposition format.
+    Metrics,
+
+    /// Shell variable assignments, for `eval`.
+    Env,
+}
+
+/// When to color the output.
+#[derive(Deserialize, ValueEnum, Copy, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Color only when writing to a terminal, unless `NO_COLOR` is set.
+    Auto,
+
+    /// Always color.
+    Always,
+
+    /// Never color.
+    Never,
+}
+
+/// Built-in set of symbols for each level.
+#[derive(Deserialize, ValueEnum, Copy, Clone, Default, Eq, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GlyphSet {
+    /// `%`, `$`, `&`, `@`, and `#`, with `?` for errors.
+    #[default]
+    Ascii,
+
+    /// 👻, 🙂, 🔑, ⚙️, and 👑, with ❓ for errors.
+    Emoji,
+
+    /// Nerd Font icons used by prompt themes like powerlevel10k, which need a patched font.
+    Nerd,
+}
+impl GlyphSet {
+    /// Glyph for the given level, or errors.
+    pub fn glyph(self, level: Option<Permissions>) -> &'static str {
+        match (self, level) {
+            (GlyphSet::Ascii, Some(Permissions::Guest)) => "%",
+            (GlyphSet::Ascii, Some(Permissions::User)) => "$",
+            (GlyphSet::Ascii, Some(Permissions::Operator)) => "&",
+            (GlyphSet::Ascii, Some(Permissions::System)) => "@",
+            (GlyphSet::Ascii, Some(Permissions::Absolute)) => "#",
+            (GlyphSet::Ascii, None) => "?",
+            (GlyphSet::Emoji, Some(Permissions::Guest)) => "\u{1f47b}",
+            (GlyphSet::Emoji, Some(Permissions::User)) => "\u{1f642}",
+            (GlyphSet::Emoji, Some(Permissions::Operator)) => "\u{1f511}",
+            (GlyphSet::Emoji, Some(Permissions::System)) => "\u{2699}\u{fe0f}",
+            (GlyphSet::Emoji, Some(Permissions::Absolute)) => "\u{1f451}",
+            (GlyphSet::Emoji, None) => "\u{2753}",
+            // nf-fa-user_secret, nf-oct-person, nf-fa-key, nf-fa-cog, nf-fa-bolt, and
+            // nf-fa-question
+            (GlyphSet::Nerd, Some(Permissions::Guest)) => "\u{f21b}",
+            (GlyphSet::Nerd, Some(Permissions::User)) => "\u{f415}",
+            (GlyphSet::Nerd, Some(Permissions::Operator)) => "\u{f084}",
+            (GlyphSet::Nerd, Some(Permissions::System)) => "\u{f013}",
+            (GlyphSet::Nerd, Some(Permissions::Absolute)) => "\u{f0e7}",
+            (GlyphSet::Nerd, None) => "\u{f128}",
+        }
+    }
+}
+
+/// Values given separately for each level, and for errors.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PerLevel<T> {
+    /// Value for [`Permissions::Guest`].
+    pub guest: Option<T>,
+
+    /// Value for [`Permissions::User`].
+    pub user: Option<T>,
+
+    /// Value for [`Permissions::Operator`].
+    pub operator: Option<T>,
+
+    /// Value for [`Permissions::System`].
+    pub system: Option<T>,
+
+    /// Value for [`Permissions::Absolute`].
+    pub absolute: Option<T>,
+
+    /// Value for errors.
+    pub unknown: Option<T>,
+}
+impl<T> PerLevel<T> {
+    /// Value for the given level, or errors.
+    pub fn get(&self, level: Option<Permissions>) -> Option<&T> {
+        match level {
+            Some(Permissions::Guest) => self.guest.as_ref(),
+            Some(Permissions::User) => self.user.as_ref(),
+            Some(Permissions::Operator) => self.operator.as_ref(),
+            Some(Permissions::System) => self.system.as_ref(),
+            Some(Permissions::Absolute) => self.absolute.as_ref(),
+            None => self.unknown.as_ref(),
+        }
+    }
+
+    /// Iterates over all values which were given.
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        [
+            &self.guest,
+            &self.user,
+            &self.operator,
+            &self.system,
+            &self.absolute,
+            &self.unknown,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// Colors used for powerline segments.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Powerline {
+    /// 256-color palette indices used for the foreground of each level.
+    pub foreground: PerLevel<u8>,
+
+    /// 256-color palette indices used for the background of each level.
+    pub background: PerLevel<u8>,
+}
+
+/// Contents of the configuration file.
+///
+/// Flags passed on the command line always take precedence over these.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Format used when no format flag is given.
+    pub format: Option<Format>,
+
+    /// When to color the output, if `--color` isn't given.
+    pub color: Option<ColorChoice>,
+
+    /// ANSI SGR parameters (like `1;31`) used to color each level.
+    pub colors: PerLevel<String>,
+
+    /// Replacements for the symbol of each level.
+    pub glyphs: PerLevel<String>,
+
+    /// Built-in set of symbols used for levels without a replacement, if `--glyphs` isn't given.
+    pub glyph_set: Option<GlyphSet>,
+
+    /// Colors used for powerline segments.
+    pub powerline: Powerline,
+
+    /// Suffix added to symbols when running in a container, which also enables detection.
+    pub container_suffix: Option<String>,
+
+    /// Suffix added to the current user's symbol when the controlling terminal is owned by another
+    /// user, which also enables detection.
+    pub switched_suffix: Option<String>,
+
+    /// Whether users who can elevate are shown as operators, which is the default; if `false`,
+    /// they're shown as users, like before the operator level existed.
+    pub operator: Option<bool>,
+
+    /// Whether the conventional `nobody` user IDs are always guests, which is the default.
+    pub nobody_is_guest: Option<bool>,
+
+    /// Shared library used to classify the current user and users given by name, instead of the
+    /// built-in backend.
+    ///
+    /// This requires the `plugins` feature.
+    pub plugin: Option<PathBuf>,
+}
+
+/// Error loading the configuration file.
+#[derive(Debug)]
+pub enum Error {
+    /// Error reading the file.
+    Read {
+        /// Path to the file.
+        path: PathBuf,
+
+        /// The error.
+        error: io::Error,
+    },
+
+    /// Error parsing the file.
+    Parse {
+        /// Path to the file.
+        path: PathBuf,
+
+        /// The error.
+        error: toml::de::Error,
+    },
+
+    /// Invalid color in the file.
+    InvalidColor {
+        /// Path to the file.
+        path: PathBuf,
+
+        /// The color.
+        color: String,
+    },
+
+    /// Invalid glyph in the file.
+    InvalidGlyph {
+        /// Path to the file.
+        path: PathBuf,
+
+        /// The glyph.
+        glyph: String,
+    },
+
+    /// Invalid value for the `OMST_GLYPHS` environment variable.
+    InvalidGlyphsVar {
+        /// The value.
+        value: String,
+    },
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Read { path, error } => {
+                write!(f, "could not read {} due to error: {error}", path.display())
+            }
+            Error::Parse { path, error } => {
+                write!(
+                    f,
+                    "could not parse {} due to error: {error}",
+                    path.display()
+                )
+            }
+            Error::InvalidColor { path, color } => write!(
+                f,
+                "color {color:?} in {} is not a list of ANSI SGR parameters",
+                path.display()
+            ),
+            Error::InvalidGlyph { path, glyph } => write!(
+                f,
+                "glyph {glyph:?} in {} is empty or contains control characters",
+                path.display()
+            ),
+            Error::InvalidGlyphsVar { value } => write!(
+                f,
+                "OMST_GLYPHS must be four or five comma-separated glyphs without control \
+                 characters, but was {value:?}"
+            ),
+        }
+    }
+}
+
+/// Location of the configuration file.
+///
+/// This is `%APPDATA%\omst\config.toml` on Windows, and `$XDG_CONFIG_HOME/omst/config.toml`
+/// (defaulting to `~/.config/omst/config.toml`) everywhere else.
+pub fn path() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let dir = env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    dir.map(|dir| dir.join("omst").join("config.toml"))
+}
+
+/// Whether a glyph is valid, i.e. is nonempty and has no control characters.
+fn is_valid_glyph(glyph: &str) -> bool {
+    !glyph.is_empty() && !glyph.chars().any(char::is_control)
+}
+
+/// Loads the configuration file, if it exists, and applies overrides from the environment.
+///
+/// Currently, the only such override is `OMST_GLYPHS`, a comma-separated list of glyphs for
+/// [`Permissions::Guest`], [`Permissions::User`], [`Permissions::System`], and
+/// [`Permissions::Absolute`], in that order, optionally followed by one for
+/// [`Permissions::Operator`], which comes last since it was added later.
+pub fn load() -> Result<Config, Error> {
+    let mut config = load_file()?;
+    if let Some(value) = env::var_os("OMST_GLYPHS") {
+        let value = value.to_string_lossy();
+        let glyphs: Vec<&str> = value.split(',').collect();
+        let (guest, user, system, absolute, operator) = match glyphs[..] {
+            [guest, user, system, absolute] => (guest, user, system, absolute, None),
+            [guest, user, system, absolute, operator] => {
+                (guest, user, system, absolute, Some(operator))
+            }
+            _ => {
+                return Err(Error::InvalidGlyphsVar {
+                    value: value.into_owned(),
+                })
+            }
+        };
+        if !glyphs.iter().all(|glyph| is_valid_glyph(glyph)) {
+            return Err(Error::InvalidGlyphsVar {
+                value: value.into_owned(),
+            });
+        }
+        config.glyphs.guest = Some(guest.to_owned());
+        config.glyphs.user = Some(user.to_owned());
+        config.glyphs.system = Some(system.to_owned());
+        config.glyphs.absolute = Some(absolute.to_owned());
+        if let Some(operator) = operator {
+            config.glyphs.operator = Some(operator.to_owned());
+        }
+    }
+    Ok(config)
+}
+
+/// Loads the configuration file, if it exists.
+fn load_file() -> Result<Config, Error> {
+    let Some(path) = path() else {
+        return Ok(Config::default());
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+        Err(error) => return Err(Error::Read { path, error }),
+    };
+    let config: Config = match toml::from_str(&text) {
+        Ok(config) => config,
+        Err(error) => return Err(Error::Parse { path, error }),
+    };
+    if let Some(color) = config
+        .colors
+        .iter()
+        .find(|color| color.is_empty() || !color.bytes().all(|b| b.is_ascii_digit() || b == b';'))
+    {
+        return Err(Error::InvalidColor {
+            path,
+            color: color.clone(),
+        });
+    }
+    if let Some(glyph) = config
+        .glyphs
+        .iter()
+        .chain(&config.container_suffix)
+        .chain(&config.switched_suffix)
+        .find(|glyph| !is_valid_glyph(glyph))
+    {
+        return Err(Error::InvalidGlyph {
+            path,
+            glyph: glyph.clone(),
+        });
+    }
+    Ok(config)
+}
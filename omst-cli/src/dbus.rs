@@ -0,0 +1,57 @@
+//! D-Bus service exposing the result on the session bus.
+use std::io;
+use zbus::blocking::connection;
+use zbus::interface;
+use zbus::object_server::SignalEmitter;
+
+/// Well-known name of the service, which is also the name of its interface.
+const NAME: &str = "org.omst.Whomst";
+
+/// Path of the object implementing the interface.
+const PATH: &str = "/org/omst/Whomst";
+
+/// Symbol and level name most recently determined.
+struct Whomst {
+    /// Symbol for the level, or an error.
+    symbol: String,
+
+    /// Name of the level, or `unknown`.
+    level: String,
+}
+
+#[interface(name = "org.omst.Whomst")]
+impl Whomst {
+    /// Returns the symbol and name of the level.
+    fn get(&self) -> (String, String) {
+        (self.symbol.clone(), self.level.clone())
+    }
+
+    /// Emitted with the new symbol and name of the level whenever either changes.
+    #[zbus(signal)]
+    async fn changed(emitter: &SignalEmitter<'_>, symbol: &str, level: &str) -> zbus::Result<()>;
+}
+
+/// Serves the result on the session bus, checking `state` for changes whenever `wait` returns.
+///
+/// `state` returns the symbol and name of the level, in that order.
+pub fn serve(
+    mut wait: impl FnMut() -> io::Result<()>,
+    mut state: impl FnMut() -> (String, String),
+) -> zbus::Result<()> {
+    let (symbol, level) = state();
+    let conn = connection::Builder::session()?
+        .name(NAME)?
+        .serve_at(PATH, Whomst { symbol, level })?
+        .build()?;
+    let iface = conn.object_server().interface::<_, Whomst>(PATH)?;
+    loop {
+        wait()?;
+        let (symbol, level) = state();
+        let mut whomst = iface.get_mut();
+        if whomst.symbol != symbol || whomst.level != level {
+            zbus::block_on(Whomst::changed(iface.signal_emitter(), &symbol, &level))?;
+            whomst.symbol = symbol;
+            whomst.level = level;
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Diagnostics printed by `omst doctor`.
+#[cfg(not(windows))]
+use omst::shadow;
+#[cfg(windows)]
+use omst::Permissions;
+#[cfg(not(windows))]
+use std::fs::File;
+use std::io::{self, Write};
+#[cfg(not(windows))]
+use std::path::Path;
+
+/// Result of a single check.
+struct Check {
+    /// What was checked.
+    name: &'static str,
+
+    /// Details on success, or the problem and a suggested fix on failure.
+    outcome: Result<String, (String, &'static str)>,
+}
+
+/// Path to the file the `shadow-utils` backend reads.
+#[cfg(not(windows))]
+const LOGIN_DEFS: &str = "/etc/login.defs";
+
+/// Checks the prerequisites of the `shadow-utils` backend.
+#[cfg(not(windows))]
+fn checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    checks.push(Check {
+        name: "login.defs is readable",
+        outcome: File::open(LOGIN_DEFS)
+            .map(|_| format!("opened {LOGIN_DEFS}"))
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => (
+                    format!("{LOGIN_DEFS} does not exist"),
+                    "install shadow-utils (or your distribution's equivalent), or create the \
+                     file with UID_MIN and UID_MAX definitions",
+                ),
+                _ => (
+                    format!("could not open {LOGIN_DEFS}: {err}"),
+                    "make sure the file is readable by all users, usually with mode 644",
+                ),
+            }),
+    });
+
+    let range = shadow::uid_range_with(Path::new("/"), shadow::Duplicates::default(), None);
+    let defaulted = matches!(
+        range,
+        Err(shadow::Error::InvalidDef {
+            problem: shadow::Problem::Missing,
+            ..
+        })
+    );
+    let range = if defaulted {
+        shadow::uid_range()
+    } else {
+        range
+    };
+    checks.push(Check {
+        name: "UID_MIN and UID_MAX are valid",
+        outcome: match &range {
+            Ok(range) => Ok(format!(
+                "UID_MIN {}, UID_MAX {}{}",
+                range.start(),
+                range.end(),
+                if defaulted {
+                    " (at least one is missing, so the defaults from login.defs(5) are used)"
+                } else {
+                    ""
+                }
+            )),
+            Err(err) => Err((
+                err.to_string(),
+                "define both UID_MIN and UID_MAX as numbers in /etc/login.defs",
+            )),
+        },
+    });
+    if let Ok(range) = &range {
+        checks.push(Check {
+            name: "UID_MIN and UID_MAX are sane",
+            outcome: if range.start() > range.end() {
+                Err((
+                    "UID_MIN is greater than UID_MAX, so no user is an ordinary user".to_owned(),
+                    "swap the values of UID_MIN and UID_MAX in /etc/login.defs",
+                ))
+            } else if *range.start() == 0 {
+                Err((
+                    "UID_MIN is 0, so system users are classified as ordinary users".to_owned(),
+                    "set UID_MIN to the first ordinary UID, usually 1000, in /etc/login.defs",
+                ))
+            } else {
+                Ok(format!(
+                    "{} UIDs are ordinary users",
+                    range.end() - range.start() + 1
+                ))
+            },
+        });
+    }
+
+    let uid = shadow::euid();
+    let users = shadow::users();
+    checks.push(Check {
+        name: "user database is readable",
+        outcome: match users.iter().find(|user| user.uid == uid) {
+            Some(user) => Ok(format!(
+                "{} entries, including {} (uid {uid})",
+                users.len(),
+                user.name
+            )),
+            None if users.is_empty() => Err((
+                "no users were listed".to_owned(),
+                "check the passwd entry in /etc/nsswitch.conf, and that /etc/passwd is readable",
+            )),
+            None => Err((
+                format!("uid {uid} is not in the user database"),
+                "check that the service providing your account (like sssd or LDAP) is running",
+            )),
+        },
+    });
+
+    checks.push(Check {
+        name: "current user is classified",
+        outcome: omst::omst()
+            .map(|level| format!("{level} ({})", level.be()))
+            .map_err(|err| (err.to_string(), "fix the failed checks above")),
+    });
+    checks
+}
+
+/// Checks the prerequisites of the Windows backend.
+#[cfg(windows)]
+fn checks() -> Vec<Check> {
+    vec![
+        Check {
+            name: "account privileges are readable",
+            outcome: omst::winapi::omst()
+                .map(Permissions::from)
+                .map(|level| format!("{level} ({})", level.be()))
+                .map_err(|err| {
+                    (
+                        err.to_string(),
+                        "make sure the Workstation service is running, and that the domain \
+                         controller is reachable for domain accounts",
+                    )
+                }),
+        },
+        Check {
+            name: "process token is readable",
+            outcome: omst::winapi::elevation()
+                .map(|elevation| elevation.to_string().replace('\n', ", "))
+                .map_err(|err| {
+                    (
+                        err.to_string(),
+                        "make sure the process isn't running with a restricted token",
+                    )
+                }),
+        },
+    ]
+}
+
+/// Runs all checks, printing the results, and returns whether all of them passed.
+pub fn run(out: &mut impl Write) -> io::Result<bool> {
+    let backend = omst::backend();
+    writeln!(out, "backend: {} ({})", backend.name, backend.cost)?;
+    let mut passed = true;
+    for Check { name, outcome } in checks() {
+        match outcome {
+            Ok(details) => writeln!(out, "[pass] {name}: {details}")?,
+            Err((problem, fix)) => {
+                passed = false;
+                writeln!(out, "[FAIL] {name}: {problem}")?;
+                writeln!(out, "       fix: {fix}")?;
+            }
+        }
+    }
+    Ok(passed)
+}
@@ -0,0 +1,323 @@
+//! Translations of backend errors and `--explain` output.
+//!
+//! Catalogs are embedded from `locales/*.ftl`, which use a small subset of Fluent syntax, and the
+//! language is chosen from the usual locale environment variables. Anything without a translation,
+//! including the level names and the Windows backend, falls back to English.
+#[cfg(not(windows))]
+use omst::shadow::{self, Def, Error, Operation, Origin, Problem, Provenance, UidRange};
+use omst::Report;
+use std::env;
+use std::error::Error as StdError;
+use std::fmt;
+#[cfg(not(windows))]
+use std::io;
+#[cfg(not(windows))]
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Embedded catalogs, by language code.
+const CATALOGS: &[(&str, &str)] = &[
+    ("de", include_str!("../locales/de.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+/// Messages for a single language.
+pub struct Catalog {
+    /// Messages, by ID.
+    #[cfg_attr(windows, allow(dead_code))]
+    messages: Vec<(&'static str, &'static str)>,
+}
+
+/// Catalog for the user's language, if there is one.
+pub fn catalog() -> Option<&'static Catalog> {
+    static CATALOG: OnceLock<Option<Catalog>> = OnceLock::new();
+    CATALOG
+        .get_or_init(|| {
+            languages()
+                .into_iter()
+                .find_map(|lang| CATALOGS.iter().find(|(code, _)| *code == lang))
+                .map(|(_, source)| Catalog::parse(source))
+        })
+        .as_ref()
+}
+
+/// Languages the user prefers, in order, like gettext.
+///
+/// `LC_ALL`, `LC_MESSAGES`, and `LANG` determine the locale, and if it isn't `C` or `POSIX`,
+/// `LANGUAGE` may list several languages which take precedence over it.
+fn languages() -> Vec<String> {
+    let var = |name| env::var(name).ok().filter(|value| !value.is_empty());
+    let Some(locale) = var("LC_ALL")
+        .or_else(|| var("LC_MESSAGES"))
+        .or_else(|| var("LANG"))
+    else {
+        return Vec::new();
+    };
+    if locale == "C" || locale == "POSIX" || locale.starts_with("C.") {
+        return Vec::new();
+    }
+    let mut languages: Vec<String> = var("LANGUAGE")
+        .iter()
+        .flat_map(|list| list.split(':'))
+        .chain([locale.as_str()])
+        .map(|locale| {
+            // `de_AT.UTF-8@euro` and `de-AT` are both German
+            let end = locale.find(['_', '-', '.', '@']).unwrap_or(locale.len());
+            locale[..end].to_ascii_lowercase()
+        })
+        .filter(|lang| !lang.is_empty())
+        .collect();
+    languages.dedup();
+    languages
+}
+
+impl Catalog {
+    /// Parses a catalog.
+    ///
+    /// Only single-line messages of the form `id = message` are supported; comments and blank lines
+    /// are skipped, as are lines which aren't messages.
+    fn parse(source: &'static str) -> Catalog {
+        let messages = source
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once(" = "))
+            .map(|(id, message)| (id.trim(), message.trim()))
+            .collect();
+        Catalog { messages }
+    }
+
+    /// Formats a message, replacing each `{ $name }` with the matching argument.
+    #[cfg_attr(windows, allow(dead_code))]
+    fn format(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> Option<String> {
+        let (_, mut message) = self.messages.iter().find(|(key, _)| *key == id)?;
+        let mut out = String::new();
+        while let Some(start) = message.find('{') {
+            let Some(len) = message[start..].find('}') else {
+                break;
+            };
+            out.push_str(&message[..start]);
+            let name = message[start + 1..start + len].trim();
+            match args
+                .iter()
+                .find(|(arg, _)| Some(*arg) == name.strip_prefix('$'))
+            {
+                Some((_, value)) => out.push_str(&value.to_string()),
+                None => out.push_str(&message[start..=start + len]),
+            }
+            message = &message[start + len + 1..];
+        }
+        out.push_str(message);
+        Some(out)
+    }
+
+    /// Translates a backend error, possibly wrapped in an [`io::Error`].
+    #[cfg(not(windows))]
+    pub fn error(&self, err: &(dyn StdError + 'static)) -> Option<String> {
+        let err = match err.downcast_ref::<io::Error>() {
+            Some(err) => err.get_ref()?.downcast_ref::<Error>()?,
+            None => err.downcast_ref::<Error>()?,
+        };
+        let path = err.path().unwrap_or(Path::new("/etc/login.defs")).display();
+        match err {
+            Error::LoginDefs {
+                operation, error, ..
+            } => {
+                let id = match operation {
+                    Operation::Open => "error-login-defs-open",
+                    Operation::Read => "error-login-defs-read",
+                };
+                self.format(id, &[("path", &path), ("error", error)])
+            }
+            Error::FileTooLarge { .. } => self.format(
+                "error-file-too-large",
+                &[("path", &path), ("max", &shadow::FILE_MAX)],
+            ),
+            Error::LineTooLong { line, .. } => self.format(
+                "error-line-too-long",
+                &[("path", &path), ("line", line), ("max", &shadow::LINE_MAX)],
+            ),
+            Error::InvalidDef { def, problem, .. } => {
+                let (def, id): (&dyn fmt::Display, &dyn fmt::Display) = match def {
+                    Def::Min => (&"UID_MIN", &"UID"),
+                    Def::Max => (&"UID_MAX", &"UID"),
+                    Def::GidMin => (&"GID_MIN", &"GID"),
+                    Def::GidMax => (&"GID_MAX", &"GID"),
+                };
+                match problem {
+                    Problem::Missing => {
+                        self.format("error-def-missing", &[("path", &path), ("def", def)])
+                    }
+                    Problem::Empty => {
+                        self.format("error-def-empty", &[("path", &path), ("def", def)])
+                    }
+                    Problem::Invalid { data } => self.format(
+                        "error-def-invalid",
+                        &[("path", &path), ("def", def), ("id", id), ("value", data)],
+                    ),
+                    Problem::OutOfRange { data } => self.format(
+                        "error-def-out-of-range",
+                        &[
+                            ("path", &path),
+                            ("def", def),
+                            ("id", id),
+                            ("max", &libc::uid_t::MAX),
+                            ("value", data),
+                        ],
+                    ),
+                }
+            }
+            Error::Stat { path, error } => {
+                self.format("error-stat", &[("path", &path.display()), ("error", error)])
+            }
+            Error::Unmapped { path } => self.format("error-unmapped", &[("path", &path.display())]),
+            Error::UnknownUser { name } => self.format("error-unknown-user", &[("name", name)]),
+            Error::LookupUser { name, error } => {
+                self.format("error-lookup-user", &[("name", name), ("error", error)])
+            }
+            Error::Process { pid, error } => {
+                self.format("error-process", &[("pid", pid), ("error", error)])
+            }
+            Error::Peer { error } => self.format("error-peer", &[("error", error)]),
+        }
+    }
+
+    /// Translates a backend error; the Windows backend isn't translated.
+    #[cfg(windows)]
+    pub fn error(&self, _: &(dyn StdError + 'static)) -> Option<String> {
+        None
+    }
+
+    /// Translates why the `login.defs` of a report couldn't be used.
+    #[cfg(not(windows))]
+    fn reason(&self, report: &Report) -> Option<String> {
+        match &report.degraded {
+            None => self.format(
+                "reason-missing",
+                &[("path", &report.login_defs_path().display())],
+            ),
+            Some(err @ Error::LoginDefs { error, .. }) => {
+                let path = err.path().unwrap_or(Path::new("/etc/login.defs")).display();
+                if error.kind() == io::ErrorKind::NotFound {
+                    self.format("reason-missing", &[("path", &path)])
+                } else {
+                    self.format("reason-unreadable", &[("path", &path), ("error", error)])
+                }
+            }
+            Some(err) => self.error(err),
+        }
+    }
+
+    /// Translates where a `UID_MIN` or `UID_MAX` value came from.
+    #[cfg(not(windows))]
+    fn origin(&self, origin: Origin) -> Option<String> {
+        match origin {
+            Origin::Default => self.format("origin-default", &[]),
+            Origin::Line { line, ignored: 0 } => self.format("origin-line", &[("line", &line)]),
+            Origin::Line { line, ignored: 1 } => {
+                self.format("origin-line-ignoring-one", &[("line", &line)])
+            }
+            Origin::Line { line, ignored } => self.format(
+                "origin-line-ignoring",
+                &[("line", &line), ("count", &ignored)],
+            ),
+        }
+    }
+
+    /// Translates the single-line trace of a report, like [`Report::explain`].
+    #[cfg(not(windows))]
+    pub fn explain(&self, report: &Report) -> Option<String> {
+        let uid = &report.uid;
+        let trace = match &report.range {
+            None => match &report.home {
+                Some(home) => {
+                    let reason = self.reason(report)?;
+                    self.format(
+                        "explain-home",
+                        &[("uid", uid), ("home", home), ("reason", &reason)],
+                    )?
+                }
+                None => self.format("explain-root", &[("uid", uid)])?,
+            },
+            Some(range) => {
+                let (min, max) = (range.start(), range.end());
+                let trace = match report.result {
+                    UidRange::AboveMax if uid <= max => {
+                        self.format("explain-nobody", &[("uid", uid), ("max", max)])?
+                    }
+                    UidRange::BelowMin => {
+                        self.format("explain-below", &[("uid", uid), ("min", min)])?
+                    }
+                    UidRange::AboveMax => {
+                        self.format("explain-above", &[("uid", uid), ("max", max)])?
+                    }
+                    _ => self.format(
+                        "explain-within",
+                        &[("uid", uid), ("min", min), ("max", max)],
+                    )?,
+                };
+                let path = report.login_defs_path().display();
+                match (&report.degraded, report.provenance) {
+                    (Some(_), _) => {
+                        let reason = self.reason(report)?;
+                        self.format(
+                            "explain-from-defaults",
+                            &[("trace", &trace), ("reason", &reason)],
+                        )?
+                    }
+                    (None, Some(Provenance { min, max })) => {
+                        let (min, max) = (self.origin(min)?, self.origin(max)?);
+                        self.format(
+                            "explain-from-lines",
+                            &[
+                                ("trace", &trace),
+                                ("path", &path),
+                                ("min", &min),
+                                ("max", &max),
+                            ],
+                        )?
+                    }
+                    (None, None) => self.format(
+                        "explain-from-login-defs",
+                        &[("trace", &trace), ("path", &path)],
+                    )?,
+                }
+            }
+        };
+        let trace = match report.login_uid.filter(|login_uid| login_uid != uid) {
+            Some(login_uid) => self.format(
+                "explain-login-uid",
+                &[("trace", &trace), ("login", &login_uid)],
+            )?,
+            None => trace,
+        };
+        let trace = match report.tty_owner.filter(|tty_owner| tty_owner != uid) {
+            Some(tty_owner) => self.format(
+                "explain-tty-owner",
+                &[("trace", &trace), ("owner", &tty_owner)],
+            )?,
+            None => trace,
+        };
+        let trace = match (report.result, &report.admin_group) {
+            (UidRange::InRange, Some(group)) => self.format(
+                "explain-admin-group",
+                &[("trace", &trace), ("group", group)],
+            )?,
+            _ => trace,
+        };
+        let level = report.permissions();
+        self.format(
+            "explain-result",
+            &[
+                ("trace", &trace),
+                ("level", &level),
+                ("symbol", &level.be()),
+            ],
+        )
+    }
+
+    /// Translates the single-line trace of a report; the Windows backend isn't translated.
+    #[cfg(windows)]
+    pub fn explain(&self, _: &Report) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,90 @@
+//! Shell integration printed by `omst init`.
+use clap::ValueEnum;
+
+/// Shell to integrate with.
+#[derive(ValueEnum, Copy, Clone, Debug)]
+pub enum InitShell {
+    /// Sets `OMST_SYMBOL` via `PROMPT_COMMAND`.
+    Bash,
+
+    /// Sets `OMST_SYMBOL` via a `precmd` hook, and enables `prompt_subst`.
+    Zsh,
+
+    /// Sets `OMST_SYMBOL` and defines `omst_prompt`, for use in `fish_prompt`.
+    Fish,
+
+    /// Sets `$OMST_SYMBOL` and prepends it to the existing `prompt` function.
+    #[value(alias = "pwsh")]
+    Powershell,
+}
+
+/// Integration for bash, where `@OMST@` is the quoted path to the binary.
+const BASH: &str = r#"__omst_update() {
+    # the effective user can't change in a running shell, so, this only runs once
+    if [[ "${__omst_uid-}" != "$EUID" ]]; then
+        __omst_uid=$EUID
+        OMST_SYMBOL=$(@OMST@ --prompt bash)
+    fi
+}
+if [[ ";${PROMPT_COMMAND-};" != *";__omst_update;"* ]]; then
+    PROMPT_COMMAND="__omst_update${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
+"#;
+
+/// Integration for zsh, where `@OMST@` is the quoted path to the binary.
+const ZSH: &str = r#"__omst_update() {
+    # the effective user can't change in a running shell, so, this only runs once
+    if [[ "${__omst_uid-}" != "$EUID" ]]; then
+        __omst_uid=$EUID
+        OMST_SYMBOL=$(@OMST@ --prompt zsh)
+    fi
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd __omst_update
+setopt prompt_subst
+"#;
+
+/// Integration for fish, where `@OMST@` is the quoted path to the binary.
+const FISH: &str = r#"# the effective user can't change in a running shell, so, this only runs once
+set -g OMST_SYMBOL (@OMST@ --prompt fish)
+
+function omst_prompt --description 'Print whomst thou art'
+    printf '%s' $OMST_SYMBOL
+end
+"#;
+
+/// Integration for PowerShell, where `@OMST@` is the quoted path to the binary.
+const POWERSHELL: &str = r#"# the current user can't change in a running shell, so, this only runs once
+$global:OMST_SYMBOL = (& @OMST@ --no-newline) -join ''
+if (-not $global:__omst_prompt) {
+    $global:__omst_prompt = $function:prompt
+    function global:prompt {
+        "$global:OMST_SYMBOL " + (& $global:__omst_prompt)
+    }
+}
+"#;
+
+/// Quotes a string for a POSIX-like shell.
+pub fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quotes a string for fish, which allows escaping within single quotes.
+fn quote_fish(s: &str) -> String {
+    format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Quotes a string for PowerShell, which escapes single quotes by doubling them.
+fn quote_powershell(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Shell code which integrates `exe` with the given shell.
+pub fn script(shell: InitShell, exe: &str) -> String {
+    match shell {
+        InitShell::Bash => BASH.replace("@OMST@", &quote(exe)),
+        InitShell::Zsh => ZSH.replace("@OMST@", &quote(exe)),
+        InitShell::Fish => FISH.replace("@OMST@", &quote_fish(exe)),
+        InitShell::Powershell => POWERSHELL.replace("@OMST@", &quote_powershell(exe)),
+    }
+}
@@ -0,0 +1,86 @@
+//! Logging of errors to the system log.
+use clap::ValueEnum;
+use std::error::Error as StdError;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::process;
+
+/// System log to send errors to.
+#[derive(ValueEnum, Copy, Clone, Debug)]
+pub enum LogTarget {
+    /// The systemd journal, with structured fields.
+    Journald,
+
+    /// The local syslog daemon.
+    Syslog,
+}
+
+/// Socket for the native journald protocol.
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Socket for the local syslog daemon.
+const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Stable numeric code for an error, if it came from the backend.
+fn code(err: &io::Error) -> Option<u16> {
+    err.get_ref()?
+        .downcast_ref::<omst::shadow::Error>()
+        .map(omst::shadow::Error::code)
+}
+
+/// OS error code for an error, if it or any of its sources has one.
+fn errno(err: &io::Error) -> Option<i32> {
+    let mut source: Option<&(dyn StdError + 'static)> = Some(err);
+    while let Some(err) = source {
+        if let Some(code) = err
+            .downcast_ref::<io::Error>()
+            .and_then(io::Error::raw_os_error)
+            .or_else(|| {
+                err.downcast_ref::<omst::IoError>()
+                    .and_then(omst::IoError::raw_os_error)
+            })
+        {
+            return Some(code);
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// Appends a field to a journald message.
+///
+/// Values are always length-prefixed, so that they may contain newlines.
+fn field(buf: &mut Vec<u8>, name: &str, value: &str) {
+    buf.extend_from_slice(name.as_bytes());
+    buf.push(b'\n');
+    buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    buf.extend_from_slice(value.as_bytes());
+    buf.push(b'\n');
+}
+
+/// Logs an error to the given target, using `ident` as the program name.
+pub fn log(target: LogTarget, ident: &str, err: &io::Error) -> io::Result<()> {
+    let socket = UnixDatagram::unbound()?;
+    match target {
+        LogTarget::Journald => {
+            let mut buf = Vec::new();
+            field(&mut buf, "MESSAGE", &err.to_string());
+            field(&mut buf, "PRIORITY", "3");
+            field(&mut buf, "SYSLOG_IDENTIFIER", ident);
+            field(&mut buf, "OMST_BACKEND", omst::backend_name());
+            if let Some(code) = code(err) {
+                field(&mut buf, "OMST_CODE", &code.to_string());
+            }
+            if let Some(errno) = errno(err) {
+                field(&mut buf, "ERRNO", &errno.to_string());
+            }
+            socket.send_to(&buf, JOURNALD_SOCKET)?;
+        }
+        LogTarget::Syslog => {
+            // facility 1 (user) and severity 3 (error)
+            let message = format!("<11>{ident}[{}]: {err}", process::id());
+            socket.send_to(message.as_bytes(), SYSLOG_SOCKET)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Notification of changes to the files the backend reads, via inotify.
+use std::ffi::{CStr, CString};
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::time::Duration;
+
+/// Names of the files in the watched directory which affect the result.
+const INPUTS: &[&[u8]] = &[b"login.defs", b"passwd"];
+
+/// Events which indicate that a file was changed or replaced.
+const MASK: u32 = libc::IN_CLOSE_WRITE
+    | libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO;
+
+/// Watches a directory for changes to the inputs.
+///
+/// The directory is watched instead of the files themselves, since editors and package managers
+/// usually replace files by renaming over them, which would leave a watch on the old file.
+pub struct Watcher {
+    /// The inotify instance.
+    fd: OwnedFd,
+}
+impl Watcher {
+    /// Starts watching `dir`, which is usually `/etc`.
+    pub fn new(dir: &Path) -> io::Result<Watcher> {
+        let dir = CString::new(dir.as_os_str().as_bytes())?;
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), dir.as_ptr(), MASK) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Watcher { fd })
+    }
+
+    /// Waits for one of the inputs to change, returning whether one did.
+    ///
+    /// If `timeout` is `None`, this waits indefinitely, skipping changes to other files in the
+    /// directory. Otherwise, this waits at most until the timeout passes.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+        let timeout = timeout.map_or(-1, |timeout| {
+            timeout.as_millis().try_into().unwrap_or(libc::c_int::MAX)
+        });
+        let mut pollfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            match unsafe { libc::poll(&mut pollfd, 1, timeout) } {
+                0 => return Ok(false),
+                n if n < 0 => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+                _ if self.drain()? => return Ok(true),
+                _ if timeout >= 0 => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads all pending events, returning whether any of them affected the inputs.
+    fn drain(&mut self) -> io::Result<bool> {
+        // large enough for several events with maximum-length names
+        let mut buf = [0u8; 4096];
+        let mut changed = false;
+        loop {
+            let len =
+                unsafe { libc::read(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+            if len < 0 {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::WouldBlock => return Ok(changed),
+                    io::ErrorKind::Interrupted => continue,
+                    _ => return Err(err),
+                }
+            }
+            let buf = &buf[..len as usize];
+            let mut offset = 0;
+            while offset + mem::size_of::<libc::inotify_event>() <= buf.len() {
+                let event: libc::inotify_event = unsafe {
+                    buf.as_ptr()
+                        .add(offset)
+                        .cast::<libc::inotify_event>()
+                        .read_unaligned()
+                };
+                let name_start = offset + mem::size_of::<libc::inotify_event>();
+                let name_end = (name_start + event.len as usize).min(buf.len());
+                let name = CStr::from_bytes_until_nul(&buf[name_start..name_end])
+                    .map_or(&[][..], CStr::to_bytes);
+
+                // if the queue overflowed, we don't know what changed, so, assume everything did
+                changed |= event.mask & libc::IN_Q_OVERFLOW != 0 || INPUTS.contains(&name);
+                offset = name_end;
+            }
+        }
+    }
+}
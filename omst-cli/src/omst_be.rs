@@ -0,0 +1,20 @@
+use std::io;
+use std::process::ExitCode;
+
+mod cli;
+mod config;
+#[cfg(all(not(windows), feature = "dbus"))]
+mod dbus;
+mod doctor;
+#[cfg(feature = "i18n")]
+mod i18n;
+mod init;
+#[cfg(unix)]
+mod log;
+#[cfg(target_os = "linux")]
+mod notify;
+mod template;
+
+fn main() -> io::Result<ExitCode> {
+    cli::main(true)
+}
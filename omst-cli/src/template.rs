@@ -0,0 +1,191 @@
+//! Output templates given via `--format`.
+use clap::ValueEnum;
+use std::mem;
+use std::str::FromStr;
+
+/// Value which can be substituted into a template, or printed alone with `--field`.
+#[derive(ValueEnum, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Field {
+    /// The symbol for the level, `{symbol}` in templates.
+    Symbol,
+
+    /// The name of the level, or `unknown`; `{name}` in templates.
+    #[value(name = "level", alias = "name")]
+    Name,
+
+    /// The user ID, or nothing; `{uid}` in templates.
+    Uid,
+
+    /// The name of the backend, `{backend}` in templates.
+    Backend,
+
+    /// The error, or nothing; `{error}` in templates.
+    Error,
+}
+impl FromStr for Field {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Field, String> {
+        match s {
+            "symbol" => Ok(Field::Symbol),
+            "name" => Ok(Field::Name),
+            "uid" => Ok(Field::Uid),
+            "backend" => Ok(Field::Backend),
+            "error" => Ok(Field::Error),
+            _ => Err(format!(
+                "unknown placeholder {{{s}}}; expected one of {{symbol}}, {{name}}, {{uid}}, \
+                 {{backend}}, or {{error}}"
+            )),
+        }
+    }
+}
+
+/// Piece of a template.
+#[derive(Clone, Debug)]
+enum Piece {
+    /// Text printed as-is.
+    Literal(String),
+
+    /// Placeholder replaced with a value.
+    Field(Field),
+}
+
+/// Template made of literal text and `{field}` placeholders.
+///
+/// Braces can be escaped by doubling them, like `{{` and `}}`.
+#[derive(Clone, Debug)]
+pub struct Template {
+    /// Pieces, in order.
+    pieces: Vec<Piece>,
+}
+impl FromStr for Template {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Template, String> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let Some(end) = rest.find('}') else {
+                        return Err("unclosed `{`; use `{{` for a literal brace".to_owned());
+                    };
+                    let field = rest[..end].parse()?;
+                    chars = rest[end + 1..].chars();
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Field(field));
+                }
+                '}' => return Err("unmatched `}`; use `}}` for a literal brace".to_owned()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Template { pieces })
+    }
+}
+impl From<Field> for Template {
+    /// Template made of the single placeholder.
+    fn from(field: Field) -> Template {
+        Template {
+            pieces: vec![Piece::Field(field)],
+        }
+    }
+}
+impl Template {
+    /// Whether the template contains the given placeholder.
+    pub fn has(&self, field: Field) -> bool {
+        self.pieces
+            .iter()
+            .any(|piece| matches!(piece, Piece::Field(f) if *f == field))
+    }
+
+    /// Renders the template, calling `value` for each placeholder.
+    pub fn render(&self, mut value: impl FnMut(Field) -> String) -> String {
+        let mut out = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Literal(text) => out.push_str(text),
+                Piece::Field(field) => out.push_str(&value(*field)),
+            }
+        }
+        out
+    }
+}
+
+#[test]
+fn parse_and_render() {
+    let render = |s: &str| {
+        s.parse::<Template>().map(|template| {
+            template.render(|field| match field {
+                Field::Symbol => "$".to_owned(),
+                Field::Name => "user".to_owned(),
+                Field::Uid => "1000".to_owned(),
+                Field::Backend => "shadow".to_owned(),
+                Field::Error => String::new(),
+            })
+        })
+    };
+    assert_eq!(render("").as_deref(), Ok(""));
+    assert_eq!(render("plain").as_deref(), Ok("plain"));
+    assert_eq!(
+        render("{symbol} {name} ({uid}, {backend}){error}").as_deref(),
+        Ok("$ user (1000, shadow)")
+    );
+    assert_eq!(render("{uid}{uid}").as_deref(), Ok("10001000"));
+    assert_eq!(render("é {symbol} ✓").as_deref(), Ok("é $ ✓"));
+
+    assert!("{uid}".parse::<Template>().unwrap().has(Field::Uid));
+    assert!(!"{uid}".parse::<Template>().unwrap().has(Field::Error));
+    assert!(!"{{error}}".parse::<Template>().unwrap().has(Field::Error));
+    assert!(Template::from(Field::Error).has(Field::Error));
+}
+
+#[test]
+fn escaped_braces() {
+    let render = |s: &str| s.parse::<Template>().unwrap().render(|_| "$".to_owned());
+    assert_eq!(render("{{symbol}}"), "{symbol}");
+    assert_eq!(render("{{{symbol}}}"), "{$}");
+    assert_eq!(render("}}{{"), "}{");
+    assert_eq!(render("{{{{"), "{{");
+    assert_eq!(render("a{{b}}c"), "a{b}c");
+}
+
+#[test]
+fn invalid_templates() {
+    let error = |s: &str| s.parse::<Template>().unwrap_err();
+    let unknown = |name: &str| {
+        format!(
+            "unknown placeholder {{{name}}}; expected one of {{symbol}}, {{name}}, {{uid}}, \
+             {{backend}}, or {{error}}"
+        )
+    };
+    assert_eq!(error("{level}"), unknown("level"));
+    assert_eq!(error("{Symbol}"), unknown("Symbol"));
+    assert_eq!(error("{ symbol }"), unknown(" symbol "));
+    assert_eq!(error("{}"), unknown(""));
+    assert_eq!(error("{symbol} {nope}"), unknown("nope"));
+
+    let unclosed = "unclosed `{`; use `{{` for a literal brace";
+    assert_eq!(error("{"), unclosed);
+    assert_eq!(error("{symbol"), unclosed);
+    assert_eq!(error("{symbol} {"), unclosed);
+    assert_eq!(error("{{{"), unclosed);
+
+    let unmatched = "unmatched `}`; use `}}` for a literal brace";
+    assert_eq!(error("}"), unmatched);
+    assert_eq!(error("a}b"), unmatched);
+    assert_eq!(error("{symbol}}"), unmatched);
+    assert_eq!(error("}}}"), unmatched);
+}
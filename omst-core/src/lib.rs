@@ -0,0 +1,510 @@
+//! Core types shared by the `omst` backends.
+//!
+//! Most users want the `omst` crate instead, which re-exports everything here along with the
+//! backend for the current platform.
+//!
+//! Without the default `std` feature, this crate is `no_std`, and only provides [`Permissions`] and
+//! the types which don't need an allocator or OS errors.
+#![cfg_attr(not(feature = "std"), no_std)]
+use core::cmp::Ordering;
+use core::error::Error as StdError;
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(feature = "std")]
+mod os;
+
+#[cfg(feature = "std")]
+pub use crate::os::{Account, IoError};
+
+/// Information about a backend, as returned by `omst::backend`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub struct BackendInfo {
+    /// Short name of the implementation, matching its module name.
+    pub name: &'static str,
+
+    /// Files and system APIs consulted to determine permissions.
+    pub sources: &'static [&'static str],
+
+    /// Most expensive thing the implementation does to determine the current user's permissions.
+    pub cost: Cost,
+}
+impl BackendInfo {
+    /// Describes a backend.
+    ///
+    /// Since this struct is non-exhaustive, backend crates must use this to construct it.
+    #[inline]
+    pub const fn new(
+        name: &'static str,
+        sources: &'static [&'static str],
+        cost: Cost,
+    ) -> BackendInfo {
+        BackendInfo {
+            name,
+            sources,
+            cost,
+        }
+    }
+}
+
+/// How expensive a backend is, as described by [`BackendInfo::cost`].
+///
+/// Costs are ordered from cheapest to most expensive, so that callers like prompts can decide at
+/// runtime whether to call the backend directly: for example, only backends which are at most
+/// [`Cost::Files`] are usually fast enough to call before every prompt.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Cost {
+    /// Doesn't consult the OS at all.
+    Pure,
+
+    /// Only makes system calls which can't block, like `geteuid`.
+    Syscalls,
+
+    /// Reads local files, which can block on slow disks or network filesystems.
+    Files,
+
+    /// Calls local system services, like the Windows security accounts manager, which can block
+    /// while they're busy.
+    Services,
+
+    /// Makes network calls, which can block for a long time.
+    Network,
+}
+impl Cost {
+    /// Whether the backend doesn't consult the OS at all.
+    #[inline]
+    pub fn is_pure(self) -> bool {
+        self == Cost::Pure
+    }
+}
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Cost::Pure => "pure",
+            Cost::Syscalls => "system calls",
+            Cost::Files => "reads files",
+            Cost::Services => "calls system services",
+            Cost::Network => "makes network calls",
+        })
+    }
+}
+
+/// Summary of a user's permissions.
+///
+/// This indicator is purely informational and should not be assumed to have any level of security.
+///
+/// Permissions are ordered from least to most access, i.e. in the order they're declared.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Permissions {
+    /// Restricted permissions.
+    ///
+    /// Usually, these users will be ephemeral and have their files deleted after logging out.
+    ///
+    /// # System-specific behavior
+    ///
+    /// On POSIX-based systems, this includes at least the `nobody` user,
+    /// but may include other dedicated guest users.
+    ///
+    /// On Windows, this is specifically guest users.
+    Guest = b'%',
+
+    /// Ordinary user permissions.
+    ///
+    /// Users that represent a real person will have this permission level.
+    User = b'$',
+
+    /// Ordinary user permissions, for users who can elevate to absolute permissions.
+    ///
+    /// These users aren't currently elevated, but could be, without needing anyone else's
+    /// credentials. Callers which don't care about the difference can use
+    /// [`flatten`](Self::flatten) to treat them as [`Permissions::User`].
+    ///
+    /// # System-specific behavior
+    ///
+    /// On unix-family systems, these are ordinary users in a group like `wheel` or `sudo`.
+    ///
+    /// On Windows, these are administrators whose process token isn't elevated, because of UAC.
+    Operator = b'&',
+
+    /// System service permissions.
+    ///
+    /// These are users dedicated to running system services who may have elevated privileges, but
+    /// do not have absolute system access.
+    ///
+    /// # System-specific behavior
+    ///
+    /// This is mostly only available on unix-family systems, for users with a UID below `UID_MIN`.
+    /// In most cases, this means a UID below 1000, but some systems may start allocating ordinary
+    /// users at UID 500.
+    System = b'@',
+
+    /// Absolute permissions.
+    ///
+    /// These users have full access to the system, to the extent that the OS allows.
+    ///
+    /// # System-specific behavior
+    ///
+    /// On unix-family systems and Android, this is the root user.
+    ///
+    /// On Windows, this refers to users with administrator privileges.
+    Absolute = b'#',
+}
+impl PartialOrd for Permissions {
+    #[inline]
+    fn partial_cmp(&self, other: &Permissions) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Permissions {
+    #[inline]
+    fn cmp(&self, other: &Permissions) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+impl Permissions {
+    /// Rank of the permissions, from least to most access.
+    #[inline]
+    fn rank(self) -> u8 {
+        match self {
+            Permissions::Guest => 0,
+            Permissions::User => 1,
+            Permissions::Operator => 2,
+            Permissions::System => 3,
+            Permissions::Absolute => 4,
+        }
+    }
+
+    /// The permissions as a single ASCII character.
+    ///
+    /// In most cases, you want to use [`be`](Self::be) instead.
+    #[inline]
+    pub fn byte(self) -> u8 {
+        self as u8
+    }
+
+    /// Permissions from the single ASCII character returned by [`byte`](Self::byte).
+    #[inline]
+    pub fn from_byte(byte: u8) -> Option<Permissions> {
+        match byte {
+            b'%' => Some(Permissions::Guest),
+            b'$' => Some(Permissions::User),
+            b'&' => Some(Permissions::Operator),
+            b'@' => Some(Permissions::System),
+            b'#' => Some(Permissions::Absolute),
+            _ => None,
+        }
+    }
+
+    /// The permissions as a single character.
+    ///
+    /// Most often used as `omst().be()`.
+    #[inline]
+    pub fn be(self) -> char {
+        self.byte() as char
+    }
+
+    /// The permissions, with [`Permissions::Operator`] treated as [`Permissions::User`].
+    ///
+    /// This is useful for compatibility with callers which only expect the original four levels.
+    #[inline]
+    pub fn flatten(self) -> Permissions {
+        match self {
+            Permissions::Operator => Permissions::User,
+            level => level,
+        }
+    }
+
+    /// Human-readable, single-sentence explanation of the permissions.
+    ///
+    /// Unlike the [`Display`](fmt::Display) implementation, which is just the name, this is meant
+    /// for help text and user interfaces, and may be reworded between releases.
+    pub fn description(self) -> &'static str {
+        match self {
+            Permissions::Guest => {
+                "Restricted permissions: usually ephemeral users whose files are deleted after \
+                 logging out."
+            }
+            Permissions::User => "Ordinary user permissions: users that represent a real person.",
+            Permissions::Operator => {
+                "Operator permissions: ordinary users who aren't elevated, but can elevate to \
+                 absolute permissions."
+            }
+            Permissions::System => {
+                "System service permissions: users dedicated to running system services, who may \
+                 have elevated privileges, but do not have absolute system access."
+            }
+            Permissions::Absolute => {
+                "Absolute permissions: users with full access to the system, to the extent that \
+                 the OS allows."
+            }
+        }
+    }
+}
+impl fmt::Display for Permissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Permissions::Guest => "guest",
+            Permissions::User => "user",
+            Permissions::Operator => "operator",
+            Permissions::System => "system",
+            Permissions::Absolute => "absolute",
+        })
+    }
+}
+impl FromStr for Permissions {
+    type Err = ParsePermissionsError;
+
+    /// Parses the name of the permissions, as displayed, or their symbol.
+    fn from_str(s: &str) -> Result<Permissions, ParsePermissionsError> {
+        match s {
+            "guest" | "%" => Ok(Permissions::Guest),
+            "user" | "$" => Ok(Permissions::User),
+            "operator" | "&" => Ok(Permissions::Operator),
+            "system" | "@" => Ok(Permissions::System),
+            "absolute" | "#" => Ok(Permissions::Absolute),
+            _ => Err(ParsePermissionsError),
+        }
+    }
+}
+
+/// Error returned when parsing [`Permissions`] fails.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ParsePermissionsError;
+impl fmt::Display for ParsePermissionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("expected one of guest, user, operator, system, or absolute")
+    }
+}
+impl StdError for ParsePermissionsError {}
+
+/// Kind of session the current process is running in, as returned by `omst::session`.
+///
+/// Like [`Permissions`], this is purely informational, and is detected with heuristics.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Session {
+    /// Remote login over SSH.
+    Ssh,
+
+    /// Local text console, without a graphical session.
+    Console,
+
+    /// Graphical session, like X11, Wayland, or the Windows desktop.
+    Graphical,
+
+    /// Container or chroot, entered without logging in.
+    Container,
+
+    /// Job run by `cron`.
+    Cron,
+}
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Session::Ssh => "ssh",
+            Session::Console => "console",
+            Session::Graphical => "graphical",
+            Session::Container => "container",
+            Session::Cron => "cron",
+        })
+    }
+}
+
+/// Displayed version of result for `omst-be`.
+///
+/// Works for any error type which implements [`Display`](fmt::Display), so that wrappers which
+/// map the error can still use [`display`](ResultExt::display).
+#[cfg(feature = "std")]
+pub struct DisplayResult<E = std::io::Error>(Result<Permissions, E>);
+
+/// Displayed version of result for `omst-be`.
+///
+/// Works for any error type which implements [`Display`](fmt::Display), so that wrappers which
+/// map the error can still use [`display`](ResultExt::display).
+#[cfg(not(feature = "std"))]
+pub struct DisplayResult<E>(Result<Permissions, E>);
+impl<E: fmt::Display> fmt::Display for DisplayResult<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Ok(ok) => fmt::Display::fmt(ok, f),
+            Err(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+/// Extension trait for return value of `omst::omst`.
+pub trait ResultExt: Sized {
+    /// Error type of the result.
+    type Error;
+
+    /// The permissions as a single ASCII character.
+    ///
+    /// In most cases, you want to use [`be`](Self::be) instead.
+    fn byte(self) -> u8;
+
+    /// The permissions as a single character.
+    ///
+    /// Most often used as `omst().be()`.
+    fn be(self) -> char;
+
+    /// The permissions as a displayable value.
+    ///
+    /// Will fully explain errors.
+    fn display(self) -> DisplayResult<Self::Error>;
+
+    /// The permissions, treating errors as [`Permissions::Guest`].
+    ///
+    /// This is the most conservative fallback, since it assumes the least access.
+    fn unwrap_or_guest(self) -> Permissions;
+
+    /// The permissions as a single ASCII character, using `fallback` on errors.
+    ///
+    /// [`byte`](Self::byte) is equivalent to `byte_or(b'?')`.
+    fn byte_or(self, fallback: u8) -> u8;
+
+    /// The permissions as a single character, using `fallback` on errors.
+    ///
+    /// Unlike [`byte_or`](Self::byte_or), the fallback doesn't have to be ASCII, so it can match
+    /// other glyphs used for the permissions. [`be`](Self::be) is equivalent to `be_or('?')`.
+    fn be_or(self, fallback: char) -> char;
+
+    /// Maps the permissions with the given function, leaving errors untouched.
+    fn map_level<T, F: FnOnce(Permissions) -> T>(self, f: F) -> Result<T, Self::Error>;
+
+    /// The permissions as an option, printing any error to standard error.
+    #[cfg(feature = "std")]
+    fn ok_logged(self) -> Option<Permissions>
+    where
+        Self::Error: fmt::Display;
+}
+impl<E> ResultExt for Result<Permissions, E> {
+    type Error = E;
+
+    #[inline]
+    fn byte(self) -> u8 {
+        self.byte_or(b'?')
+    }
+    #[inline]
+    fn be(self) -> char {
+        self.be_or('?')
+    }
+    #[inline]
+    fn display(self) -> DisplayResult<E> {
+        DisplayResult(self)
+    }
+    #[inline]
+    fn unwrap_or_guest(self) -> Permissions {
+        self.unwrap_or(Permissions::Guest)
+    }
+    #[inline]
+    fn byte_or(self, fallback: u8) -> u8 {
+        self.map_or(fallback, Permissions::byte)
+    }
+    #[inline]
+    fn be_or(self, fallback: char) -> char {
+        self.map_or(fallback, Permissions::be)
+    }
+    #[inline]
+    fn map_level<T, F: FnOnce(Permissions) -> T>(self, f: F) -> Result<T, E> {
+        self.map(f)
+    }
+    #[cfg(feature = "std")]
+    fn ok_logged(self) -> Option<Permissions>
+    where
+        E: fmt::Display,
+    {
+        match self {
+            Ok(ok) => Some(ok),
+            Err(err) => {
+                std::eprintln!("omst: {err}");
+                None
+            }
+        }
+    }
+}
+
+#[test]
+fn fallbacks() {
+    let err: Result<Permissions, &str> = Err("oops");
+    assert_eq!(err.byte(), b'?');
+    assert_eq!(err.byte_or(b'!'), b'!');
+    assert_eq!(err.be(), '?');
+    assert_eq!(err.be_or('\u{2753}'), '\u{2753}');
+    assert_eq!(Ok::<_, &str>(Permissions::System).be_or('!'), '@');
+    assert_eq!(err.unwrap_or_guest(), Permissions::Guest);
+    assert_eq!(
+        Ok::<_, &str>(Permissions::User).map_level(|p| p.be()),
+        Ok('$')
+    );
+}
+
+#[test]
+fn parse() {
+    for level in [
+        Permissions::Guest,
+        Permissions::User,
+        Permissions::Operator,
+        Permissions::System,
+        Permissions::Absolute,
+    ] {
+        assert_eq!(level.to_string().parse(), Ok(level));
+        assert_eq!(level.be().to_string().parse(), Ok(level));
+    }
+    assert_eq!("root".parse::<Permissions>(), Err(ParsePermissionsError));
+}
+
+#[test]
+#[cfg(feature = "arbitrary")]
+fn arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let levels: Vec<Permissions> = (0..=u8::MAX)
+        .map(|byte| Permissions::arbitrary(&mut Unstructured::new(&[byte; 4])).unwrap())
+        .collect();
+    assert!(levels.contains(&Permissions::Guest));
+    assert!(levels.contains(&Permissions::Absolute));
+}
+
+#[test]
+fn description() {
+    for level in [
+        Permissions::Guest,
+        Permissions::User,
+        Permissions::Operator,
+        Permissions::System,
+        Permissions::Absolute,
+    ] {
+        let (summary, _) = level.description().split_once(':').unwrap();
+        assert!(summary.to_ascii_lowercase().ends_with(" permissions"));
+        assert!(level.description().ends_with('.'));
+    }
+}
+
+#[test]
+fn order() {
+    assert!(Permissions::Guest < Permissions::User);
+    assert!(Permissions::User < Permissions::Operator);
+    assert!(Permissions::Operator < Permissions::System);
+    assert_eq!(Permissions::Operator.flatten(), Permissions::User);
+    assert!(Permissions::System < Permissions::Absolute);
+}
+
+#[test]
+fn cost() {
+    assert!(Cost::Pure.is_pure());
+    assert!(!Cost::Syscalls.is_pure());
+    assert!(Cost::Pure < Cost::Syscalls);
+    assert!(Cost::Files < Cost::Services);
+    assert!(Cost::Services < Cost::Network);
+}
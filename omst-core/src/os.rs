@@ -0,0 +1,184 @@
+//! Types which hold OS errors or allocate, and so need the standard library.
+use crate::Permissions;
+use core::fmt;
+use std::error::Error as StdError;
+use std::io;
+use std::string::String;
+
+/// Account on the system, as returned by `omst::accounts`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Account {
+    /// Name of the account.
+    pub name: String,
+
+    /// User ID of the account, on systems which have them.
+    pub uid: Option<u32>,
+
+    /// Permissions of the account.
+    pub permissions: io::Result<Permissions>,
+
+    /// Whether the permissions were only guessed, because the usual sources couldn't be used.
+    ///
+    /// This is set by the backends after constructing the account, and is `false` by default.
+    pub degraded: bool,
+}
+impl Account {
+    /// Describes an account.
+    ///
+    /// Since this struct is non-exhaustive, backend crates must use this to construct it.
+    #[inline]
+    pub fn new(name: String, uid: Option<u32>, permissions: io::Result<Permissions>) -> Account {
+        Account {
+            name,
+            uid,
+            permissions,
+            degraded: false,
+        }
+    }
+}
+
+/// I/O error stored by the backends' errors, keeping only its kind, OS error code, and message.
+///
+/// Unlike [`io::Error`], this can be cloned and compared, so that the errors containing it can be
+/// too. It converts from and into [`io::Error`], although any custom payload is lost.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct IoError {
+    /// Kind of the error.
+    kind: io::ErrorKind,
+
+    /// OS error code, if the error came from the OS.
+    code: Option<i32>,
+
+    /// Static description of the error, if it didn't come from the OS.
+    message: Option<&'static str>,
+}
+impl IoError {
+    /// Creates an error of the given kind with a static description.
+    #[inline]
+    pub const fn new(kind: io::ErrorKind, message: &'static str) -> IoError {
+        IoError {
+            kind,
+            code: None,
+            message: Some(message),
+        }
+    }
+
+    /// Creates an error from an OS error code, like [`io::Error::from_raw_os_error`].
+    #[inline]
+    pub fn from_raw_os_error(code: i32) -> IoError {
+        IoError::from(io::Error::from_raw_os_error(code))
+    }
+
+    /// Creates an error from the last OS error, like [`io::Error::last_os_error`].
+    #[inline]
+    pub fn last_os_error() -> IoError {
+        IoError::from(io::Error::last_os_error())
+    }
+
+    /// Kind of the error.
+    #[inline]
+    pub fn kind(&self) -> io::ErrorKind {
+        self.kind
+    }
+
+    /// OS error code, if the error came from the OS.
+    #[inline]
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.code
+    }
+}
+impl From<io::ErrorKind> for IoError {
+    #[inline]
+    fn from(kind: io::ErrorKind) -> IoError {
+        IoError {
+            kind,
+            code: None,
+            message: None,
+        }
+    }
+}
+impl From<io::Error> for IoError {
+    #[inline]
+    fn from(err: io::Error) -> IoError {
+        IoError {
+            kind: err.kind(),
+            code: err.raw_os_error(),
+            message: None,
+        }
+    }
+}
+impl From<IoError> for io::Error {
+    #[inline]
+    fn from(err: IoError) -> io::Error {
+        match err {
+            IoError {
+                code: Some(code), ..
+            } => io::Error::from_raw_os_error(code),
+            IoError {
+                kind,
+                message: Some(message),
+                ..
+            } => io::Error::new(kind, message),
+            IoError { kind, .. } => io::Error::from(kind),
+        }
+    }
+}
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.code, self.message) {
+            (Some(code), _) => fmt::Display::fmt(&io::Error::from_raw_os_error(code), f),
+            (None, Some(message)) => f.write_str(message),
+            (None, None) => fmt::Display::fmt(&self.kind, f),
+        }
+    }
+}
+impl StdError for IoError {}
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for IoError {
+    /// Generates either an OS error, from an arbitrary code, or an error of one of the kinds the
+    /// backends produce without an OS error.
+    ///
+    /// Static descriptions aren't generated, since they can't be borrowed from the input.
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<IoError> {
+        const KINDS: &[io::ErrorKind] = &[
+            io::ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied,
+            io::ErrorKind::InvalidData,
+            io::ErrorKind::InvalidInput,
+            io::ErrorKind::TimedOut,
+            io::ErrorKind::UnexpectedEof,
+            io::ErrorKind::Unsupported,
+            io::ErrorKind::Other,
+        ];
+        Ok(match u.arbitrary()? {
+            Some(code) => IoError::from_raw_os_error(code),
+            None => IoError::from(*u.choose(KINDS)?),
+        })
+    }
+}
+
+#[test]
+fn io_error() {
+    let err = IoError::from_raw_os_error(2);
+    assert_eq!(err, IoError::from(io::Error::from_raw_os_error(2)));
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    assert_eq!(err.to_string(), io::Error::from_raw_os_error(2).to_string());
+    assert_eq!(io::Error::from(err).raw_os_error(), Some(2));
+
+    let err = IoError::new(io::ErrorKind::InvalidData, "bad data");
+    assert_eq!(err.to_string(), "bad data");
+    assert_eq!(io::Error::from(err).kind(), io::ErrorKind::InvalidData);
+    assert_ne!(err, IoError::from(io::ErrorKind::InvalidData));
+}
+
+#[test]
+#[cfg(feature = "arbitrary")]
+fn arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let err = IoError::arbitrary(&mut Unstructured::new(&[1, 2, 0, 0, 0])).unwrap();
+    assert!(err.raw_os_error().is_some());
+    let err = IoError::arbitrary(&mut Unstructured::new(&[0, 0])).unwrap();
+    assert_eq!(err.raw_os_error(), None);
+}
@@ -0,0 +1,3089 @@
+//! Implementation for `shadow-utils`.
+//!
+//! This is the `omst::shadow` module; most users want the `omst` crate instead, which picks the
+//! backend for the current platform.
+#![cfg(not(windows))]
+#![warn(unsafe_op_in_unsafe_fn)]
+use atoi::atoi;
+use omst_core::{Account, BackendInfo, Cost, IoError, Permissions, Session};
+use std::error::Error as StdError;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read};
+use std::mem::MaybeUninit;
+use std::ops::RangeInclusive;
+use std::os::fd::{AsFd, AsRawFd};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::ptr;
+use std::sync::{Mutex, PoisonError};
+
+/// Information about this implementation.
+pub const BACKEND: BackendInfo = BackendInfo::new(
+    "shadow",
+    &["geteuid", "/etc/login.defs", "getgroups", "/etc/group"],
+    Cost::Files,
+);
+
+/// UID range from `/etc/login.defs`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum UidRange {
+    /// Above `UID_MAX`.
+    AboveMax = b'%',
+
+    /// Inside `UID_MIN..=UID_MAX` range.
+    InRange = b'$',
+
+    /// Below `UID_MIN`.
+    BelowMin = b'@',
+
+    /// UID 0, root.
+    Zero = b'#',
+}
+impl From<UidRange> for Permissions {
+    #[inline]
+    fn from(range: UidRange) -> Permissions {
+        match range {
+            UidRange::AboveMax => Permissions::Guest,
+            UidRange::InRange => Permissions::User,
+            UidRange::BelowMin => Permissions::System,
+            UidRange::Zero => Permissions::Absolute,
+        }
+    }
+}
+
+/// Operation performed on `/etc/login.defs`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Operation {
+    /// Opening the file.
+    Open,
+
+    /// Reading the file.
+    Read,
+}
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Operation::Open => "open",
+            Operation::Read => "read",
+        })
+    }
+}
+
+/// Definition in `/etc/login.defs`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Def {
+    /// `UID_MIN`.
+    Min,
+
+    /// `UID_MAX`.
+    Max,
+
+    /// `GID_MIN`.
+    GidMin,
+
+    /// `GID_MAX`.
+    GidMax,
+}
+impl Def {
+    /// Name of the definition, as it appears in the file.
+    fn key(self) -> &'static str {
+        match self {
+            Def::Min => "UID_MIN",
+            Def::Max => "UID_MAX",
+            Def::GidMin => "GID_MIN",
+            Def::GidMax => "GID_MAX",
+        }
+    }
+}
+impl Def {
+    /// Kind of ID the definition is for, `UID` or `GID`.
+    fn id(self) -> &'static str {
+        match self {
+            Def::Min | Def::Max => "UID",
+            Def::GidMin | Def::GidMax => "GID",
+        }
+    }
+}
+impl fmt::Display for Def {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.key())
+    }
+}
+
+/// Problem with a definition in `/etc/login.defs`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Problem {
+    /// Definition was missing.
+    Missing,
+
+    /// Definition was provided, but its value was empty, like `UID_MIN ""`.
+    Empty,
+
+    /// Definition was not a valid UID, or GID for `GID_MIN` and `GID_MAX`.
+    Invalid {
+        /// Actual bytes of the value.
+        data: Value,
+    },
+
+    /// Definition was a number, but a minimum, like `UID_MIN`, was larger than the largest ID on
+    /// this system.
+    ///
+    /// Maximums, like `UID_MAX`, are instead clamped to the largest ID, since all IDs are below them
+    /// either way.
+    OutOfRange {
+        /// Actual bytes of the value.
+        data: Value,
+    },
+}
+
+/// Bytes of an invalid definition, stored inline so that creating an error doesn't allocate.
+///
+/// Values longer than [`Value::CAPACITY`] are truncated.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Value {
+    /// Buffer containing the bytes.
+    buf: [u8; Value::CAPACITY],
+
+    /// Number of bytes in the buffer.
+    len: u8,
+
+    /// Whether the value was truncated.
+    truncated: bool,
+}
+impl Value {
+    /// Maximum number of bytes stored.
+    pub const CAPACITY: usize = 32;
+
+    /// Copies up to [`Value::CAPACITY`] bytes of a value.
+    pub fn new(data: &[u8]) -> Value {
+        let len = data.len().min(Value::CAPACITY);
+        let mut buf = [0; Value::CAPACITY];
+        buf[..len].copy_from_slice(&data[..len]);
+        Value {
+            buf,
+            len: len as u8,
+            truncated: data.len() > len,
+        }
+    }
+
+    /// Stored bytes of the value.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..usize::from(self.len)]
+    }
+
+    /// Whether the value was longer than [`Value::CAPACITY`], and was truncated.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{self}\"")
+    }
+}
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_bytes().escape_ascii())?;
+        if self.truncated {
+            f.write_str("...")?;
+        }
+        Ok(())
+    }
+}
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Value {
+    /// Generates a value from arbitrary bytes, which are truncated like [`Value::new`].
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Value> {
+        let len = u.int_in_range(0..=Value::CAPACITY + 1)?;
+        Ok(Value::new(u.bytes(len)?))
+    }
+}
+impl fmt::Display for Problem {
+    /// Describes the problem without knowing the definition, calling the value an ID.
+    ///
+    /// [`Error`] instead names the kind of ID, like `UID`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Described(self, "ID").fmt(f)
+    }
+}
+
+/// [`Problem`] with a definition for the given kind of ID, like `UID`.
+struct Described<'a>(&'a Problem, &'a str);
+impl fmt::Display for Described<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Described(problem, id) = self;
+        match problem {
+            Problem::Missing => write!(f, "was missing"),
+            Problem::Empty => write!(f, "was empty"),
+            Problem::Invalid { data } => write!(f, "was not a valid {id} ({data})"),
+            Problem::OutOfRange { data } => write!(
+                f,
+                "was larger than the largest {id}, {} ({data})",
+                libc::uid_t::MAX
+            ),
+        }
+    }
+}
+
+/// Generates an arbitrary path for [`Error`].
+#[cfg(feature = "arbitrary")]
+fn arbitrary_path(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Box<Path>> {
+    Ok(u.arbitrary::<std::path::PathBuf>()?.into_boxed_path())
+}
+
+/// Generates an arbitrary path of `login.defs` in an alternate root, for [`Error`] and [`Report`].
+#[cfg(feature = "arbitrary")]
+fn arbitrary_sysroot_path(
+    u: &mut arbitrary::Unstructured<'_>,
+) -> arbitrary::Result<Option<Box<Path>>> {
+    Ok(u.arbitrary::<Option<std::path::PathBuf>>()?
+        .map(std::path::PathBuf::into_boxed_path))
+}
+
+/// Error that might occur when getting permissions. (`shadow-utils` implementation)
+///
+/// Errors from reading the live `/etc/login.defs`, which are the only ones [`omst`] can return,
+/// never allocate: invalid values are stored inline as a [`Value`], and only the paths of alternate
+/// roots are stored. The names and paths stored by other errors are only allocated when the error
+/// occurs, and without any spare capacity.
+///
+/// I/O errors are stored as an [`IoError`], so that errors can be cloned and compared.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Error {
+    /// Error reading `/etc/login.defs`.
+    LoginDefs {
+        /// What operation caused the error.
+        operation: Operation,
+
+        /// The error.
+        error: IoError,
+
+        /// Path that was opened, if it wasn't the live `/etc/login.defs`, like with
+        /// [`uid_range_in`].
+        ///
+        /// This is `None` for the live file, so that the usual case doesn't allocate; see
+        /// [`Error::path`] for the path either way.
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_sysroot_path))]
+        path: Option<Box<Path>>,
+    },
+
+    /// `/etc/login.defs` is at least [`FILE_MAX`] bytes long.
+    FileTooLarge {
+        /// Path that was opened, like for [`Error::LoginDefs`].
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_sysroot_path))]
+        path: Option<Box<Path>>,
+    },
+
+    /// Line in `/etc/login.defs` is longer than [`LINE_MAX`] bytes.
+    LineTooLong {
+        /// Number of the line, starting at 1.
+        line: usize,
+
+        /// Path that was opened, like for [`Error::LoginDefs`].
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_sysroot_path))]
+        path: Option<Box<Path>>,
+    },
+
+    /// Invalid definition in `/etc/login.defs`.
+    InvalidDef {
+        /// Which definition was invalid.
+        def: Def,
+
+        /// What the problem was.
+        problem: Problem,
+
+        /// Path that was opened, like for [`Error::LoginDefs`].
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_sysroot_path))]
+        path: Option<Box<Path>>,
+    },
+
+    /// Error reading the owner of a file.
+    Stat {
+        /// Path to the file.
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_path))]
+        path: Box<Path>,
+
+        /// The error.
+        error: IoError,
+    },
+
+    /// Owner of a file is not mapped to a local user.
+    ///
+    /// The kernel reports these owners as the overflow UID (usually 65534, `nobody`), which would
+    /// otherwise be misreported as [`UidRange::AboveMax`].
+    Unmapped {
+        /// Path to the file.
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_path))]
+        path: Box<Path>,
+    },
+
+    /// User does not exist in the user database.
+    UnknownUser {
+        /// Name of the user.
+        name: Box<str>,
+    },
+
+    /// Error looking up a user in the user database.
+    LookupUser {
+        /// Name of the user.
+        name: Box<str>,
+
+        /// The error.
+        error: IoError,
+    },
+
+    /// Error determining the owner of a process.
+    Process {
+        /// ID of the process.
+        pid: u32,
+
+        /// The error.
+        error: IoError,
+    },
+
+    /// Error determining the credentials of the peer of a socket.
+    Peer {
+        /// The error.
+        error: IoError,
+    },
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::LoginDefs {
+                operation,
+                error,
+                path,
+            } => write!(
+                f,
+                "could not {operation} {} due to error: {error}",
+                login_defs_path(path).display()
+            ),
+            Error::FileTooLarge { path } => write!(
+                f,
+                "{} is at least {FILE_MAX} bytes long",
+                login_defs_path(path).display()
+            ),
+            Error::LineTooLong { line, path } => write!(
+                f,
+                "line {line} of {} is longer than {LINE_MAX} bytes",
+                login_defs_path(path).display()
+            ),
+            Error::InvalidDef { def, problem, path } => write!(
+                f,
+                "{def} in {} {}",
+                login_defs_path(path).display(),
+                Described(problem, def.id())
+            ),
+            Error::Stat { path, error } => {
+                write!(f, "could not stat {} due to error: {error}", path.display())
+            }
+            Error::Unmapped { path } => {
+                write!(
+                    f,
+                    "owner of {} is not mapped to a local user",
+                    path.display()
+                )
+            }
+            Error::UnknownUser { name } => write!(f, "user {name} does not exist"),
+            Error::LookupUser { name, error } => {
+                write!(f, "could not look up user {name} due to error: {error}")
+            }
+            Error::Process { pid, error } => {
+                write!(
+                    f,
+                    "could not find owner of process {pid} due to error: {error}"
+                )
+            }
+            Error::Peer { error } => {
+                write!(
+                    f,
+                    "could not find credentials of socket peer due to error: {error}"
+                )
+            }
+        }
+    }
+}
+impl StdError for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::LoginDefs { error, .. }
+            | Error::Stat { error, .. }
+            | Error::LookupUser { error, .. }
+            | Error::Process { error, .. }
+            | Error::Peer { error } => Some(error),
+            Error::FileTooLarge { .. }
+            | Error::LineTooLong { .. }
+            | Error::InvalidDef { .. }
+            | Error::Unmapped { .. }
+            | Error::UnknownUser { .. } => None,
+        }
+    }
+}
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    /// Serializes the error as a struct with `kind`, `message`, `os_code`, and `path` fields.
+    ///
+    /// The `kind` field is one of `login_defs_missing`, `login_defs_open`, `login_defs_read`,
+    /// `login_defs_too_large`, `line_too_long`, `def_missing`, `def_empty`, `def_invalid`,
+    /// `def_out_of_range`, `stat`, `unmapped`, `unknown_user`, `lookup_user`, `process`, or `peer`,
+    /// and will not change between releases.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let (kind, os_code) = match self {
+            Error::LoginDefs { error, .. } => {
+                let kind = match self.code() {
+                    100 => "login_defs_missing",
+                    101 => "login_defs_open",
+                    _ => "login_defs_read",
+                };
+                (kind, error.raw_os_error())
+            }
+            Error::FileTooLarge { .. } => ("login_defs_too_large", None),
+            Error::LineTooLong { .. } => ("line_too_long", None),
+            Error::InvalidDef { problem, .. } => {
+                let kind = match problem {
+                    Problem::Missing => "def_missing",
+                    Problem::Empty => "def_empty",
+                    Problem::Invalid { .. } => "def_invalid",
+                    Problem::OutOfRange { .. } => "def_out_of_range",
+                };
+                (kind, None)
+            }
+            Error::Stat { error, .. } => ("stat", error.raw_os_error()),
+            Error::Unmapped { .. } => ("unmapped", None),
+            Error::UnknownUser { .. } => ("unknown_user", None),
+            Error::LookupUser { error, .. } => ("lookup_user", error.raw_os_error()),
+            Error::Process { error, .. } => ("process", error.raw_os_error()),
+            Error::Peer { error } => ("peer", error.raw_os_error()),
+        };
+        let path = self.path();
+
+        let mut state = serializer.serialize_struct("Error", 4)?;
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("message", &format_args!("{self}"))?;
+        state.serialize_field("os_code", &os_code)?;
+        state.serialize_field("path", &path)?;
+        state.end()
+    }
+}
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+    /// Code of the form `omst::N`, where `N` is the value of [`Error::code`].
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(format!("omst::{}", Error::code(self))))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let path = self.path().unwrap_or(Path::new(LOGIN_DEFS)).display();
+        let help = match self {
+            Error::LoginDefs { .. } if Error::code(self) == 100 => format!(
+                "install shadow-utils (or your distribution's equivalent), or create {path} with \
+                 UID_MIN and UID_MAX definitions"
+            ),
+            Error::LoginDefs { .. } => {
+                format!("make sure {path} is readable by all users, usually with mode 644")
+            }
+            Error::FileTooLarge { .. } => {
+                format!("make sure {path} is an ordinary configuration file")
+            }
+            Error::LineTooLong { .. } => {
+                "shorten the line, since shadow-utils can't read it either".to_owned()
+            }
+            Error::InvalidDef {
+                problem: Problem::Invalid { .. },
+                ..
+            } => format!(
+                "set it to a decimal, octal (prefixed with 0), or hexadecimal (prefixed with 0x) \
+                 number in {path}"
+            ),
+            Error::InvalidDef {
+                def,
+                problem: Problem::OutOfRange { .. },
+                ..
+            } => format!("lower {def} in {path}, usually to 1000"),
+            Error::InvalidDef { def, .. } => {
+                format!("define it as a number in {path}, like {def} 1000")
+            }
+            Error::Stat { .. } => {
+                "make sure the file exists and its directory is accessible".to_owned()
+            }
+            Error::Unmapped { .. } => {
+                "check the ID mapping of the user namespace or mount, or of the NFS server"
+                    .to_owned()
+            }
+            Error::UnknownUser { .. } => "check the name, and that the service providing the \
+                                          account (like sssd or LDAP) is running"
+                .to_owned(),
+            Error::LookupUser { .. } => "check the passwd entry in /etc/nsswitch.conf, and that \
+                                         the services it lists are running"
+                .to_owned(),
+            Error::Process { .. } => {
+                "make sure the process exists, and that /proc is mounted".to_owned()
+            }
+            Error::Peer { .. } => {
+                "make sure the socket is a connected Unix domain socket".to_owned()
+            }
+        };
+        Some(Box::new(help))
+    }
+}
+impl From<Error> for io::Error {
+    #[inline]
+    fn from(err: Error) -> io::Error {
+        match err {
+            Error::UnknownUser { .. } => io::Error::new(ErrorKind::NotFound, err),
+            _ => io::Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
+impl Error {
+    fn invalid_def(def: Def, problem: Problem, path: Option<&Path>) -> Error {
+        Error::InvalidDef {
+            def,
+            problem,
+            path: path.map(Box::from),
+        }
+    }
+
+    fn login_defs(
+        operation: Operation,
+        path: Option<&Path>,
+    ) -> impl FnOnce(io::Error) -> Error + '_ {
+        move |error| Error::LoginDefs {
+            operation,
+            error: error.into(),
+            path: path.map(Box::from),
+        }
+    }
+
+    /// Path of the file the error is about, if it's about one.
+    ///
+    /// Errors reading or parsing `login.defs` are about `/etc/login.defs`, unless an alternate
+    /// root was used to open it.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::LoginDefs { path, .. }
+            | Error::FileTooLarge { path }
+            | Error::LineTooLong { path, .. }
+            | Error::InvalidDef { path, .. } => Some(login_defs_path(path)),
+            Error::Stat { path, .. } | Error::Unmapped { path } => Some(path),
+            Error::UnknownUser { .. }
+            | Error::LookupUser { .. }
+            | Error::Process { .. }
+            | Error::Peer { .. } => None,
+        }
+    }
+
+    /// Stable numeric code for this error.
+    ///
+    /// These values will not change between releases, and are distinct from the codes used by
+    /// other backends:
+    ///
+    /// | Code | Meaning                                                    |
+    /// |------|------------------------------------------------------------|
+    /// | 100  | `/etc/login.defs` does not exist                           |
+    /// | 101  | `/etc/login.defs` could not be opened for any other reason |
+    /// | 102  | `/etc/login.defs` could not be read                        |
+    /// | 103  | `/etc/login.defs` was too large                            |
+    /// | 104  | a line in `/etc/login.defs` was too long                   |
+    /// | 110  | `UID_MIN`, `UID_MAX`, `GID_MIN`, or `GID_MAX` was missing  |
+    /// | 111  | `UID_MIN`, `UID_MAX`, `GID_MIN`, or `GID_MAX` was empty    |
+    /// | 112  | a definition was not a valid UID, or GID for `GID_*`       |
+    /// | 113  | `UID_MIN` or `GID_MIN` was larger than the largest ID      |
+    /// | 120  | file owner could not be read                               |
+    /// | 121  | file owner is not mapped to a local user                   |
+    /// | 130  | user does not exist                                        |
+    /// | 131  | user could not be looked up                                |
+    /// | 140  | owner of a process could not be determined                 |
+    /// | 150  | credentials of a socket peer could not be determined       |
+    pub fn code(&self) -> u16 {
+        match self {
+            Error::LoginDefs {
+                operation: Operation::Open,
+                error,
+                ..
+            } if error.kind() == ErrorKind::NotFound => 100,
+            Error::LoginDefs {
+                operation: Operation::Open,
+                ..
+            } => 101,
+            Error::LoginDefs {
+                operation: Operation::Read,
+                ..
+            } => 102,
+            Error::FileTooLarge { .. } => 103,
+            Error::LineTooLong { .. } => 104,
+            Error::InvalidDef { problem, .. } => match problem {
+                Problem::Missing => 110,
+                Problem::Empty => 111,
+                Problem::Invalid { .. } => 112,
+                Problem::OutOfRange { .. } => 113,
+            },
+            Error::Stat { .. } => 120,
+            Error::Unmapped { .. } => 121,
+            Error::UnknownUser { .. } => 130,
+            Error::LookupUser { .. } => 131,
+            Error::Process { .. } => 140,
+            Error::Peer { .. } => 150,
+        }
+    }
+}
+
+/// Loads the `UID_MIN..=UID_MAX` range from `login.defs`.
+///
+/// This can be passed to [`classify`] to classify many users without rereading the file. If either
+/// value is defined more than once, the last definition is used, like `shadow-utils` does; use
+/// [`uid_range_with`] to use the first definition instead.
+///
+/// If either value isn't defined, the default from [`DEFAULT_RANGE`] is used.
+pub fn uid_range() -> Result<RangeInclusive<libc::uid_t>, Error> {
+    uid_range_in(Path::new("/"))
+}
+
+/// Loads the `UID_MIN..=UID_MAX` range from `login.defs` in an alternate root.
+///
+/// This reads `etc/login.defs` relative to `sysroot`, so that UIDs can be classified for a system
+/// that's mounted somewhere else, like an image being built or a system being rescued.
+pub fn uid_range_in(sysroot: &Path) -> Result<RangeInclusive<libc::uid_t>, Error> {
+    uid_range_with(sysroot, Duplicates::default(), Some(DEFAULT_RANGE))
+}
+
+/// Values used for `UID_MIN` and `UID_MAX` when they aren't defined, as documented in
+/// `login.defs(5)`.
+pub const DEFAULT_RANGE: RangeInclusive<libc::uid_t> = 1000..=60000;
+
+/// Loads the `GID_MIN..=GID_MAX` range from `login.defs`.
+///
+/// This is parsed the same way as [`uid_range`], and can be passed to [`classify_group`]. If either
+/// value isn't defined, the default from [`DEFAULT_GID_RANGE`] is used.
+pub fn gid_range() -> Result<RangeInclusive<libc::gid_t>, Error> {
+    load_defs(
+        Path::new("/"),
+        [Def::GidMin, Def::GidMax],
+        Duplicates::default(),
+        Some(DEFAULT_GID_RANGE),
+    )
+    .map(|(range, _)| range)
+}
+
+/// Values used for `GID_MIN` and `GID_MAX` when they aren't defined, as documented in
+/// `login.defs(5)`.
+pub const DEFAULT_GID_RANGE: RangeInclusive<libc::gid_t> = 1000..=60000;
+
+/// Which definition is used when `UID_MIN` or `UID_MAX` is defined more than once.
+///
+/// In both cases, only the definition that's used is validated, so other definitions may be empty
+/// or invalid without causing an error.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Duplicates {
+    /// Use the first definition, which lets parsing stop as soon as both have been found.
+    First,
+
+    /// Use the last definition, which requires reading the entire file.
+    ///
+    /// This matches `getdef` from `shadow-utils`, which stores each definition as it's read,
+    /// replacing any earlier one, and only parses the value that's left when it's looked up.
+    #[default]
+    Last,
+}
+
+/// Where a `UID_MIN` or `UID_MAX` value came from.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Origin {
+    /// The value wasn't defined, so a default was used.
+    #[default]
+    Default,
+
+    /// The value was defined on a line of `login.defs`.
+    Line {
+        /// Line number, starting from 1.
+        line: usize,
+
+        /// Number of other definitions that were ignored.
+        ///
+        /// With [`Duplicates::First`], the rest of the file isn't read once both values have been
+        /// found, so later definitions may not be counted.
+        ignored: usize,
+    },
+}
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => f.write_str("defaulted"),
+            Origin::Line { line, ignored: 0 } => write!(f, "on line {line}"),
+            Origin::Line { line, ignored: 1 } => {
+                write!(f, "on line {line}, ignoring 1 other definition")
+            }
+            Origin::Line { line, ignored } => {
+                write!(f, "on line {line}, ignoring {ignored} other definitions")
+            }
+        }
+    }
+}
+
+/// Where the `UID_MIN` and `UID_MAX` values of a range came from.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Provenance {
+    /// Origin of `UID_MIN`.
+    pub min: Origin,
+
+    /// Origin of `UID_MAX`.
+    pub max: Origin,
+}
+
+/// Loads the `UID_MIN..=UID_MAX` range from `login.defs` in an alternate root, choosing between
+/// duplicate definitions as given.
+///
+/// Values which aren't defined are taken from `defaults`, or, if it's `None`, cause an
+/// [`Error::InvalidDef`] with [`Problem::Missing`]. See [`uid_range_in`] for how the root is used.
+pub fn uid_range_with(
+    sysroot: &Path,
+    duplicates: Duplicates,
+    defaults: Option<RangeInclusive<libc::uid_t>>,
+) -> Result<RangeInclusive<libc::uid_t>, Error> {
+    load(sysroot, duplicates, defaults).map(|(range, _)| range)
+}
+
+/// Loads the `UID_MIN..=UID_MAX` range from `login.defs`, along with where its values came from.
+fn load(
+    sysroot: &Path,
+    duplicates: Duplicates,
+    defaults: Option<RangeInclusive<libc::uid_t>>,
+) -> Result<(RangeInclusive<libc::uid_t>, Provenance), Error> {
+    load_defs(sysroot, [Def::Min, Def::Max], duplicates, defaults)
+}
+
+/// Loads the range between the given pair of definitions from `login.defs`, along with where its
+/// values came from.
+fn load_defs(
+    sysroot: &Path,
+    defs: [Def; 2],
+    duplicates: Duplicates,
+    defaults: Option<RangeInclusive<libc::uid_t>>,
+) -> Result<(RangeInclusive<libc::uid_t>, Provenance), Error> {
+    // joining paths allocates, and the live system is by far the most common case
+    let path = (sysroot != Path::new("/")).then(|| sysroot.join("etc/login.defs"));
+    let mut file = File::open(path.as_deref().unwrap_or(Path::new(LOGIN_DEFS)))
+        .map_err(Error::login_defs(Operation::Open, path.as_deref()))?;
+
+    // a single read usually returns the entire file, and the next one confirms that we're done
+    let mut buf = [0; FILE_MAX];
+    let mut len = 0;
+    loop {
+        match file.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(Error::login_defs(Operation::Read, path.as_deref())(err)),
+        }
+        if len == buf.len() {
+            // this also stops us from reading forever if the file is actually a device
+            return Err(Error::FileTooLarge {
+                path: path.as_deref().map(Box::from),
+            });
+        }
+    }
+    parse_defs(&buf[..len], path.as_deref(), defs, duplicates, defaults)
+}
+
+/// Path of the live `login.defs`.
+const LOGIN_DEFS: &str = "/etc/login.defs";
+
+/// Path of `login.defs`, given the path stored for an alternate root, if any.
+fn login_defs_path(path: &Option<Box<Path>>) -> &Path {
+    path.as_deref().unwrap_or(Path::new(LOGIN_DEFS))
+}
+
+/// Limit on the size of `login.defs`, which is also the size of the buffer it's read into.
+///
+/// This is several times larger than the files most distributions ship, which lets us read the
+/// entire file at once without allocating.
+pub const FILE_MAX: usize = 64 * 1024;
+
+/// Limit on the length of a line in `login.defs`, excluding its line ending.
+///
+/// This matches the buffer that `shadow-utils` reads lines into.
+pub const LINE_MAX: usize = 1024;
+
+/// Parses the `UID_MIN..=UID_MAX` range from the contents of `login.defs`.
+#[cfg(test)]
+fn parse(
+    data: &[u8],
+    duplicates: Duplicates,
+    defaults: Option<RangeInclusive<libc::uid_t>>,
+) -> Result<RangeInclusive<libc::uid_t>, Error> {
+    parse_traced(data, duplicates, defaults).map(|(range, _)| range)
+}
+
+/// Definition found while parsing `login.defs`, which is only validated if it's used.
+#[derive(Copy, Clone)]
+struct Found<'a> {
+    /// Unparsed value.
+    val: &'a [u8],
+
+    /// Line number, starting from 1.
+    line: usize,
+
+    /// Number of other definitions that were ignored.
+    ignored: usize,
+}
+
+/// Parses the `UID_MIN..=UID_MAX` range from the contents of `login.defs`, along with where its
+/// values came from.
+#[cfg(test)]
+fn parse_traced(
+    data: &[u8],
+    duplicates: Duplicates,
+    defaults: Option<RangeInclusive<libc::uid_t>>,
+) -> Result<(RangeInclusive<libc::uid_t>, Provenance), Error> {
+    parse_defs(data, None, [Def::Min, Def::Max], duplicates, defaults)
+}
+
+/// Parses the range between the given pair of definitions from the contents of `login.defs`, along
+/// with where its values came from.
+///
+/// Errors are about `path`, which is `None` for the live `/etc/login.defs`.
+fn parse_defs(
+    data: &[u8],
+    path: Option<&Path>,
+    [min_def, max_def]: [Def; 2],
+    duplicates: Duplicates,
+    defaults: Option<RangeInclusive<libc::uid_t>>,
+) -> Result<(RangeInclusive<libc::uid_t>, Provenance), Error> {
+    let mut min = None::<Found<'_>>;
+    let mut max = None::<Found<'_>>;
+
+    // files edited on Windows may start with a byte order mark and end lines with CRLF
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+    for (idx, buf) in data.split(|b| *b == b'\n').enumerate() {
+        if let (Duplicates::First, Some(_), Some(_)) = (duplicates, min, max) {
+            break;
+        }
+        let buf = buf.strip_suffix(b"\r").unwrap_or(buf);
+        if buf.len() > LINE_MAX {
+            return Err(Error::LineTooLong {
+                line: idx + 1,
+                path: path.map(Box::from),
+            });
+        }
+
+        // like `getdef`, trailing whitespace is trimmed, and lines are split into a name and value
+        // at the first space or tab; only lines whose name starts with `#` are comments
+        let end = buf
+            .iter()
+            .rposition(|b| !is_space(*b))
+            .map_or(0, |pos| pos + 1);
+        let buf = &buf[..end];
+        let name_pos = buf.iter().position(|b| !matches!(b, b' ' | b'\t'));
+        let buf = match name_pos {
+            Some(pos) if buf[pos] != b'#' => &buf[pos..],
+            _ => continue,
+        };
+        let (key, buf) = match buf.iter().position(|b| matches!(b, b' ' | b'\t')) {
+            Some(pos) => buf.split_at(pos),
+            // names without values are ignored entirely
+            None => continue,
+        };
+
+        let slot = if key == min_def.key().as_bytes() {
+            &mut min
+        } else if key == max_def.key().as_bytes() {
+            &mut max
+        } else {
+            continue;
+        };
+        if let (Duplicates::First, Some(found)) = (duplicates, slot.as_mut()) {
+            found.ignored += 1;
+            continue;
+        }
+
+        // values may be quoted, and end at the first quote
+        let val_pos = buf.iter().position(|b| !matches!(b, b' ' | b'\t' | b'"'));
+        let buf = &buf[val_pos.unwrap_or(buf.len())..];
+        let val = match buf.iter().position(|b| *b == b'"') {
+            Some(pos) => &buf[..pos],
+            None => buf,
+        };
+
+        // `getdef` can't parse values followed by a comment, and falls back to its default, but
+        // these are common enough in edited files that we strip them instead of failing
+        let comment = (0..val.len())
+            .find(|&pos| val[pos] == b'#' && (pos == 0 || matches!(val[pos - 1], b' ' | b'\t')));
+        let val = match comment {
+            Some(pos) => val[..pos].trim_ascii_end(),
+            None => val,
+        };
+        *slot = Some(Found {
+            val,
+            line: idx + 1,
+            ignored: slot.map_or(0, |found| found.ignored + 1),
+        });
+    }
+
+    let min = value(min_def, min, defaults.as_ref().map(|range| *range.start()))
+        .map_err(|problem| Error::invalid_def(min_def, problem, path))?;
+    let max = value(max_def, max, defaults.as_ref().map(|range| *range.end()))
+        .map_err(|problem| Error::invalid_def(max_def, problem, path))?;
+    Ok((
+        min.0..=max.0,
+        Provenance {
+            min: min.1,
+            max: max.1,
+        },
+    ))
+}
+
+/// Validates the definition that was used for `def`, falling back to `default` if there wasn't one.
+fn value(
+    def: Def,
+    found: Option<Found<'_>>,
+    default: Option<libc::uid_t>,
+) -> Result<(libc::uid_t, Origin), Problem> {
+    let Some(Found { val, line, ignored }) = found else {
+        return default
+            .map(|id| (id, Origin::Default))
+            .ok_or(Problem::Missing);
+    };
+    if val.is_empty() {
+        return Err(Problem::Empty);
+    }
+
+    let Some(id) = parse_uid(val) else {
+        return Err(Problem::Invalid {
+            data: Value::new(val),
+        });
+    };
+    let id = match (&def, libc::uid_t::try_from(id)) {
+        (_, Ok(id)) => id,
+        (Def::Max | Def::GidMax, Err(_)) => libc::uid_t::MAX,
+        (Def::Min | Def::GidMin, Err(_)) => {
+            return Err(Problem::OutOfRange {
+                data: Value::new(val),
+            })
+        }
+    };
+    Ok((id, Origin::Line { line, ignored }))
+}
+
+/// Whether a byte is whitespace according to C's `isspace`, which includes vertical tabs.
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r')
+}
+
+/// Parses a UID like `shadow-utils` does, which accepts decimal numbers, octal numbers prefixed
+/// with `0`, and hexadecimal numbers prefixed with `0x`.
+///
+/// Since `uid_t` is narrower than `login.defs` values on some systems, this parses into a `u64`,
+/// saturating on overflow, and leaves the range check to the caller.
+fn parse_uid(val: &[u8]) -> Option<u64> {
+    let val = val.strip_prefix(b"+").unwrap_or(val);
+    let (digits, radix) = match val {
+        [b'0', b'x' | b'X', rest @ ..] => (rest, 16),
+        [b'0', rest @ ..] if !rest.is_empty() => (rest, 8),
+        _ => (val, 10),
+    };
+    if digits.is_empty() {
+        return None;
+    }
+    digits.iter().try_fold(0, |uid: u64, b| {
+        let digit = char::from(*b).to_digit(radix)?;
+        Some(
+            uid.saturating_mul(radix.into())
+                .saturating_add(digit.into()),
+        )
+    })
+}
+
+/// Determine [`UidRange`] based upon the user ID and the data from `shadow-utils`.
+///
+/// On all available systems, we special-case `uid == 0` as [`UidRange::Zero`], which corresponds
+/// to [`Permissions::Absolute`](Permissions::Absolute). Beyond that, the `login.defs` file
+/// provided by `shadow-utils` gives decent-enough information to help us guess the current user
+/// permissions.
+///
+/// The `UID_MIN..=UID_MAX` range defined in `login.defs` determines the range of UIDs that are free
+/// to allocate to "ordinary" users, and we assign [`UidRange::BelowMin`], [`UidRange::InRange`],
+/// and [`UidRange::AboveMax`] depending on the UID's relation to the range. These are interpreted
+/// as [`Permissions::System`], [`Permissions::User`], and [`Permissions::Guest`], respectively.
+///
+/// Although `login.defs` technically defines `SYS_UID_MIN..=SYS_UID_MAX` for system users and
+/// `SUB_UID_MIN..=SUB_UID_MAX` for "subordinate users", these often don't tend to point to the
+/// full ranges and aren't required to fill the rest of the UID range. Additionally, not all systems
+/// will actually provide these ranges, so, we basically ignore them.
+///
+/// You can see more details in the man page for `login.defs(5)` on what exactly is defined by
+/// `login.defs`, and additionally check your own systems to see how well this assumption maps to
+/// your system's UIDs.
+///
+/// If `/etc/login.defs` doesn't exist at all, the result is guessed from the user's home directory
+/// with [`guess_home`] as a last resort. If it exists but can't be read, the result is also guessed
+/// from the home directory, falling back to [`DEFAULT_RANGE`] if that doesn't work either.
+pub fn omst() -> Result<UidRange, Error> {
+    of_uid(euid())
+}
+
+/// Determine [`Permissions`] for the current user, including whether they can elevate.
+///
+/// This is [`omst`], adjusted with [`with_groups`] for the groups of the current process, so that
+/// ordinary users in one of the [`ADMIN_GROUPS`] are [`Permissions::Operator`].
+pub fn permissions() -> Result<Permissions, Error> {
+    Ok(with_groups(omst()?, &process_groups()))
+}
+
+/// Effective user ID of the current process.
+///
+/// With the `raw-syscalls` feature, this issues the `geteuid` syscall directly on Linux (for
+/// x86-64, AArch64, and RISC-V), instead of going through libc. Otherwise, this is equivalent to
+/// `libc::geteuid`.
+#[inline]
+pub fn euid() -> libc::uid_t {
+    #[cfg(all(
+        feature = "raw-syscalls",
+        target_os = "linux",
+        any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_arch = "riscv64"
+        )
+    ))]
+    {
+        raw_geteuid()
+    }
+    #[cfg(not(all(
+        feature = "raw-syscalls",
+        target_os = "linux",
+        any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_arch = "riscv64"
+        )
+    )))]
+    {
+        unsafe { libc::geteuid() }
+    }
+}
+
+/// Issues the `geteuid` syscall, which can't fail.
+#[cfg(all(
+    feature = "raw-syscalls",
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+fn raw_geteuid() -> libc::uid_t {
+    let uid: usize;
+    unsafe {
+        #[cfg(target_arch = "x86_64")]
+        core::arch::asm!(
+            "syscall",
+            inlateout("rax") 107usize => uid,
+            out("rcx") _,
+            out("r11") _,
+            options(nostack, preserves_flags, nomem),
+        );
+        #[cfg(target_arch = "aarch64")]
+        core::arch::asm!(
+            "svc 0",
+            in("x8") 175usize,
+            lateout("x0") uid,
+            options(nostack, preserves_flags, nomem),
+        );
+        #[cfg(target_arch = "riscv64")]
+        core::arch::asm!(
+            "ecall",
+            in("a7") 175usize,
+            lateout("a0") uid,
+            options(nostack, preserves_flags, nomem),
+        );
+    }
+    uid as libc::uid_t
+}
+
+/// Determine [`UidRange`] for an arbitrary user ID.
+///
+/// This uses the same logic as [`omst`], but for a user other than the current one.
+pub fn of_uid(uid: libc::uid_t) -> Result<UidRange, Error> {
+    of_uid_in(Path::new("/"), uid)
+}
+
+/// Determine [`UidRange`] for an arbitrary user ID, using `login.defs` in an alternate root.
+///
+/// See [`uid_range_in`] for how the root is used. If `login.defs` is missing or unreadable, the
+/// result is guessed like with [`of_uid`], from the home directory in `etc/passwd` of the root.
+pub fn of_uid_in(sysroot: &Path, uid: libc::uid_t) -> Result<UidRange, Error> {
+    if uid == 0 {
+        Ok(UidRange::Zero)
+    } else {
+        match uid_range_in(sysroot) {
+            Ok(range) => Ok(classify(uid, &range)),
+            Err(err) => guess_in(sysroot, uid, err).map(|(result, _)| result),
+        }
+    }
+}
+
+/// User IDs conventionally used for the `nobody` user: 65534, and `(uid_t)-2`, which is used by
+/// `nfsnobody` on some systems.
+pub const NOBODY: [libc::uid_t; 2] = [65534, libc::uid_t::MAX - 1];
+
+/// Classifies a UID against the `UID_MIN..=UID_MAX` range returned by [`uid_range`].
+///
+/// The [`NOBODY`] user IDs are always [`UidRange::AboveMax`], even if `UID_MAX` was raised above
+/// them; use [`classify_by_range`] to classify them like any other UID.
+pub fn classify(uid: libc::uid_t, range: &RangeInclusive<libc::uid_t>) -> UidRange {
+    if uid != 0 && NOBODY.contains(&uid) {
+        UidRange::AboveMax
+    } else {
+        classify_by_range(uid, range)
+    }
+}
+
+/// Classifies a UID against the `UID_MIN..=UID_MAX` range, without special-casing [`NOBODY`].
+pub fn classify_by_range(uid: libc::uid_t, range: &RangeInclusive<libc::uid_t>) -> UidRange {
+    if uid == 0 {
+        UidRange::Zero
+    } else if uid < *range.start() {
+        UidRange::BelowMin
+    } else if uid > *range.end() {
+        UidRange::AboveMax
+    } else {
+        UidRange::InRange
+    }
+}
+
+/// Guesses [`UidRange`] from a user's home directory.
+///
+/// This is a last resort for systems without `/etc/login.defs`, and is only a low-confidence
+/// guess: homes in `/home` are [`UidRange::InRange`], homes in `/var/lib` (or empty or `/`
+/// homes) are [`UidRange::BelowMin`], and `/nonexistent` is [`UidRange::AboveMax`]. Nothing is
+/// guessed for other homes.
+pub fn guess_home(home: &[u8]) -> Option<UidRange> {
+    match home {
+        b"" | b"/" => Some(UidRange::BelowMin),
+        b"/nonexistent" => Some(UidRange::AboveMax),
+        _ if home.starts_with(b"/home/") => Some(UidRange::InRange),
+        _ if home.starts_with(b"/var/lib/") => Some(UidRange::BelowMin),
+        _ => None,
+    }
+}
+
+/// Source used instead of `/etc/login.defs` when it couldn't be used.
+#[derive(Debug)]
+enum Fallback {
+    /// Home directory the result was guessed from.
+    Home(Vec<u8>),
+
+    /// [`DEFAULT_RANGE`], used when `/etc/login.defs` exists but couldn't be read.
+    Defaults,
+}
+
+/// Guesses [`UidRange`] if `/etc/login.defs` is missing or unreadable, returning the source of the
+/// guess along with the result, or the original error if nothing could be guessed.
+///
+/// The result is guessed from the user's home directory, or, if the file exists but couldn't be
+/// read, from [`DEFAULT_RANGE`], since the file most likely belongs to `shadow-utils`.
+fn guess(uid: libc::uid_t, err: Error) -> Result<(UidRange, Fallback), Error> {
+    guess_in(Path::new("/"), uid, err)
+}
+
+/// Like [`guess`], but for an alternate root, whose `etc/passwd` is read for the home directory
+/// instead of the user database of the live system.
+fn guess_in(sysroot: &Path, uid: libc::uid_t, err: Error) -> Result<(UidRange, Fallback), Error> {
+    let code = err.code();
+    if !(100..=102).contains(&code) {
+        return Err(err);
+    }
+    let home = if sysroot == Path::new("/") {
+        getent(
+            |pwd, buf, result| unsafe {
+                libc::getpwuid_r(uid, pwd, buf.as_mut_ptr(), buf.len(), result)
+            },
+            |pwd| unsafe { CStr::from_ptr(pwd.pw_dir) }.to_bytes().to_vec(),
+        )
+        .ok()
+        .flatten()
+    } else {
+        passwd_in(sysroot, uid).map(|(_, home)| home)
+    };
+    if let Some(home) = home {
+        if let Some(result) = guess_home(&home) {
+            return Ok((result, Fallback::Home(home)));
+        }
+    }
+    if code == 100 {
+        Err(err)
+    } else {
+        Ok((classify(uid, &DEFAULT_RANGE), Fallback::Defaults))
+    }
+}
+
+/// Name and home directory of a user in `etc/passwd` of an alternate root, if it's there.
+///
+/// This reads the file directly, since the user database of the live system might not be backed by
+/// files at all, and only the first entry with the user ID is used, like `getpwuid` does.
+fn passwd_in(sysroot: &Path, uid: libc::uid_t) -> Option<(String, Vec<u8>)> {
+    let passwd = fs::read(sysroot.join("etc/passwd")).ok()?;
+    passwd.split(|&b| b == b'\n').find_map(|line| {
+        // name:password:uid:gid:gecos:home:shell
+        let mut fields = line.split(|&b| b == b':');
+        let name = fields.next()?;
+        let id = fields.nth(1)?;
+        if std::str::from_utf8(id).ok()?.parse::<libc::uid_t>().ok()? != uid {
+            return None;
+        }
+        let home = fields.nth(2)?;
+        Some((String::from_utf8_lossy(name).into_owned(), home.to_vec()))
+    })
+}
+
+/// Short description of why `/etc/login.defs` couldn't be used, for [`Report::degraded`].
+struct Degradation<'a>(&'a Error);
+impl fmt::Display for Degradation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Error::LoginDefs { error, .. } if error.kind() == ErrorKind::NotFound => {
+                write!(
+                    f,
+                    "{} is missing",
+                    self.0.path().unwrap_or(Path::new(LOGIN_DEFS)).display()
+                )
+            }
+            Error::LoginDefs { error, .. } => write!(
+                f,
+                "{} is unreadable ({error})",
+                self.0.path().unwrap_or(Path::new(LOGIN_DEFS)).display()
+            ),
+            err => write!(f, "{err}"),
+        }
+    }
+}
+
+/// [`Degradation`] of a report with a guessed home, whose `login.defs` was missing unless stated
+/// otherwise.
+struct MissingOr<'a>(&'a Report);
+impl fmt::Display for MissingOr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0.degraded {
+            Some(err) => write!(f, "{}", Degradation(err)),
+            None => write!(f, "{} is missing", self.0.login_defs_path().display()),
+        }
+    }
+}
+
+/// Calls one of the `getpw*_r` or `getgr*_r` functions, growing the buffer as needed.
+///
+/// Returns `Ok(None)` if the user or group doesn't exist.
+fn getent<E, T>(
+    mut call: impl FnMut(*mut E, &mut [libc::c_char], &mut *mut E) -> libc::c_int,
+    extract: impl FnOnce(&E) -> T,
+) -> io::Result<Option<T>> {
+    let mut buf = vec![0; 1024];
+    loop {
+        let mut ent = MaybeUninit::<E>::uninit();
+        let mut result = ptr::null_mut();
+        match call(ent.as_mut_ptr(), &mut buf, &mut result) {
+            libc::ERANGE => buf.resize(buf.len() * 2, 0),
+            // some systems return these instead of a null result
+            0 | libc::ENOENT | libc::ESRCH if result.is_null() => return Ok(None),
+            0 => return Ok(Some(extract(unsafe { &*result }))),
+            err => return Err(io::Error::from_raw_os_error(err)),
+        }
+    }
+}
+
+/// Name of a user database entry.
+fn pw_name(pwd: &libc::passwd) -> String {
+    let name = unsafe { CStr::from_ptr(pwd.pw_name) };
+    String::from_utf8_lossy(name.to_bytes()).into_owned()
+}
+
+/// Looks up the name of a user in the user database.
+fn user_name(uid: libc::uid_t) -> Option<String> {
+    getent(
+        |pwd, buf, result| unsafe {
+            libc::getpwuid_r(uid, pwd, buf.as_mut_ptr(), buf.len(), result)
+        },
+        pw_name,
+    )
+    .ok()
+    .flatten()
+}
+
+/// Account in the user database.
+#[derive(Clone, Debug)]
+pub struct User {
+    /// Name of the user.
+    pub name: String,
+
+    /// ID of the user.
+    pub uid: libc::uid_t,
+
+    /// ID of the user's primary group.
+    pub gid: libc::gid_t,
+}
+
+/// Lists all accounts in the user database.
+///
+/// This uses `getpwent`, which isn't thread-safe; calls to this function are serialized, but
+/// other code calling `getpwent` at the same time may interfere with the results.
+pub fn users() -> Vec<User> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    let _guard = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let mut users = Vec::new();
+    unsafe { libc::setpwent() };
+    loop {
+        let pwd = unsafe { libc::getpwent() };
+        if pwd.is_null() {
+            break;
+        }
+        let pwd = unsafe { &*pwd };
+        users.push(User {
+            name: pw_name(pwd),
+            uid: pwd.pw_uid,
+            gid: pwd.pw_gid,
+        });
+    }
+    unsafe { libc::endpwent() };
+    users
+}
+
+/// Lists and classifies all accounts in the user database.
+///
+/// Like [`permissions_of`], ordinary users in one of the [`ADMIN_GROUPS`] are
+/// [`Permissions::Operator`]. If `/etc/login.defs` can't be used, each account is guessed like
+/// [`of_uid`] does, and marked as [`degraded`](Account::degraded); accounts which can't be guessed
+/// get the error instead.
+pub fn accounts() -> Result<Vec<Account>, Error> {
+    let range = uid_range();
+    Ok(users()
+        .into_iter()
+        .map(|User { name, uid, gid }| {
+            let (result, degraded) = match &range {
+                _ if uid == 0 => (Ok(UidRange::Zero), false),
+                Ok(range) => (Ok(classify(uid, range)), false),
+                Err(err) => (guess(uid, err.clone()).map(|(result, _)| result), true),
+            };
+            let level = result
+                .map(|result| match result {
+                    UidRange::InRange => with_groups(result, &user_groups(&name, gid)),
+                    result => result.into(),
+                })
+                .map_err(io::Error::from);
+            let ok = level.is_ok();
+            let mut account = Account::new(name, Some(uid), level);
+            account.degraded = degraded && ok;
+            account
+        })
+        .collect())
+}
+
+/// Looks up the ID of a user in the user database.
+pub fn user_id(name: &str) -> Result<libc::uid_t, Error> {
+    let unknown = || Error::UnknownUser { name: name.into() };
+    let cname = CString::new(name).map_err(|_| unknown())?;
+    getent(
+        |pwd, buf, result| unsafe {
+            libc::getpwnam_r(cname.as_ptr(), pwd, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |pwd| pwd.pw_uid,
+    )
+    .map_err(|error| Error::LookupUser {
+        name: name.into(),
+        error: error.into(),
+    })?
+    .ok_or_else(unknown)
+}
+
+/// Names of the groups whose members can conventionally elevate with `sudo`, `doas`, or `su`.
+///
+/// `wheel` is used by the BSDs, Fedora, and Arch Linux; `sudo` by Debian and Ubuntu; and `admin`
+/// by macOS and older versions of Ubuntu.
+pub const ADMIN_GROUPS: [&str; 3] = ["wheel", "sudo", "admin"];
+
+/// Effective and supplementary group IDs of the current process.
+pub fn process_groups() -> Vec<libc::gid_t> {
+    let mut groups = vec![unsafe { libc::getegid() }];
+    let len = unsafe { libc::getgroups(0, ptr::null_mut()) };
+    if len > 0 {
+        let mut buf = vec![0; len as usize];
+        let len = unsafe { libc::getgroups(len, buf.as_mut_ptr()) };
+        if len >= 0 {
+            buf.truncate(len as usize);
+            groups.extend(buf);
+        }
+    }
+    groups
+}
+
+/// Finds the first of the [`ADMIN_GROUPS`] with one of the given group IDs.
+///
+/// Groups which don't exist in the group database, or can't be looked up, are skipped.
+pub fn admin_group(groups: &[libc::gid_t]) -> Option<&'static str> {
+    ADMIN_GROUPS.into_iter().find(|name| {
+        let Ok(cname) = CString::new(*name) else {
+            return false;
+        };
+        let gid = getent(
+            |grp, buf, result| unsafe {
+                libc::getgrnam_r(cname.as_ptr(), grp, buf.as_mut_ptr(), buf.len(), result)
+            },
+            |grp: &libc::group| grp.gr_gid,
+        );
+        matches!(gid, Ok(Some(gid)) if groups.contains(&gid))
+    })
+}
+
+/// Group IDs of a user in the group database, including their primary group `gid`, from
+/// `getgrouplist`.
+///
+/// Unlike [`process_groups`], this is what the user would get by logging in now, which is how other
+/// users are checked for the [`ADMIN_GROUPS`]. If the groups can't be listed, only `gid` is
+/// returned.
+pub fn user_groups(name: &str, gid: libc::gid_t) -> Vec<libc::gid_t> {
+    // Apple platforms use `int` for the groups of this function
+    #[cfg(target_vendor = "apple")]
+    type Gid = libc::c_int;
+    #[cfg(not(target_vendor = "apple"))]
+    type Gid = libc::gid_t;
+
+    let Ok(cname) = CString::new(name) else {
+        return vec![gid];
+    };
+    let mut buf: Vec<Gid> = vec![0; 32];
+    loop {
+        let mut len = buf.len() as libc::c_int;
+        let ret =
+            unsafe { libc::getgrouplist(cname.as_ptr(), gid as Gid, buf.as_mut_ptr(), &mut len) };
+        if ret >= 0 {
+            buf.truncate(len as usize);
+            return buf.into_iter().map(|gid| gid as libc::gid_t).collect();
+        }
+
+        // glibc reports how many groups there are, but other systems don't
+        let needed = (len as usize).max(buf.len() * 2);
+        if needed > 1 << 16 {
+            return vec![gid];
+        }
+        buf.resize(needed, 0);
+    }
+}
+
+/// First of the [`ADMIN_GROUPS`] an arbitrary user is in, according to [`user_groups`].
+fn user_admin_group(uid: libc::uid_t) -> Option<&'static str> {
+    let (name, gid) = getent(
+        |pwd, buf, result| unsafe {
+            libc::getpwuid_r(uid, pwd, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |pwd: &libc::passwd| (pw_name(pwd), pwd.pw_gid),
+    )
+    .ok()
+    .flatten()?;
+    admin_group(&user_groups(&name, gid))
+}
+
+/// Adjusts a [`UidRange`] for the groups an arbitrary user is in, according to [`user_groups`].
+///
+/// This is like [`with_groups`], except that the groups are looked up, which is only done for
+/// ordinary users.
+pub fn with_user_groups(range: UidRange, uid: libc::uid_t) -> Permissions {
+    match range {
+        UidRange::InRange if user_admin_group(uid).is_some() => Permissions::Operator,
+        range => range.into(),
+    }
+}
+
+/// Determine [`Permissions`] for an arbitrary user ID, including whether they can elevate.
+///
+/// This is [`of_uid`], adjusted with [`with_user_groups`].
+pub fn permissions_of(uid: libc::uid_t) -> Result<Permissions, Error> {
+    Ok(with_user_groups(of_uid(uid)?, uid))
+}
+
+/// Determine [`Permissions`] for an arbitrary user name, including whether they can elevate.
+///
+/// This is [`of_user`], adjusted with [`with_user_groups`].
+pub fn permissions_of_user(name: &str) -> Result<Permissions, Error> {
+    permissions_of(user_id(name)?)
+}
+
+/// Adjusts a [`UidRange`] for the groups a user is in.
+///
+/// Ordinary users in one of the [`ADMIN_GROUPS`] are [`Permissions::Operator`]; otherwise, this is
+/// the same as converting the range.
+pub fn with_groups(range: UidRange, groups: &[libc::gid_t]) -> Permissions {
+    match range {
+        UidRange::InRange if admin_group(groups).is_some() => Permissions::Operator,
+        range => range.into(),
+    }
+}
+
+/// Kind of group, as determined by [`classify_group`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum GroupKind {
+    /// GID 0, the root group.
+    Root,
+
+    /// Below `GID_MIN`, like groups for system services and device access.
+    System,
+
+    /// Group created for a single user, with the same name as the user, and as their primary group.
+    UserPrivate,
+
+    /// Inside `GID_MIN..=GID_MAX`, and shared between users.
+    Shared,
+
+    /// Above `GID_MAX`, like `nogroup`.
+    AboveMax,
+}
+impl fmt::Display for GroupKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            GroupKind::Root => "root group",
+            GroupKind::System => "system group",
+            GroupKind::UserPrivate => "user-private group",
+            GroupKind::Shared => "shared group",
+            GroupKind::AboveMax => "above GID_MAX",
+        })
+    }
+}
+
+/// Group the current process is in, as returned by [`groups`].
+#[derive(Clone, Debug)]
+pub struct Group {
+    /// ID of the group.
+    pub gid: libc::gid_t,
+
+    /// Name of the group, if it's in the group database.
+    pub name: Option<String>,
+
+    /// Kind of group.
+    pub kind: GroupKind,
+}
+
+/// Classifies a group by its ID and, if it has one, its name.
+///
+/// Like `useradd` does with `USERGROUPS_ENAB`, groups in the `GID_MIN..=GID_MAX` range are
+/// user-private groups if a user with the same name has them as their primary group.
+pub fn classify_group(
+    gid: libc::gid_t,
+    name: Option<&str>,
+    range: &RangeInclusive<libc::gid_t>,
+) -> GroupKind {
+    if gid == 0 {
+        GroupKind::Root
+    } else if gid < *range.start() {
+        GroupKind::System
+    } else if gid > *range.end() {
+        GroupKind::AboveMax
+    } else if name.is_some_and(|name| user_group(name) == Some(gid)) {
+        GroupKind::UserPrivate
+    } else {
+        GroupKind::Shared
+    }
+}
+
+/// Looks up the primary group of a user in the user database.
+fn user_group(name: &str) -> Option<libc::gid_t> {
+    let cname = CString::new(name).ok()?;
+    getent(
+        |pwd, buf, result| unsafe {
+            libc::getpwnam_r(cname.as_ptr(), pwd, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |pwd: &libc::passwd| pwd.pw_gid,
+    )
+    .ok()
+    .flatten()
+}
+
+/// Looks up the name of a group in the group database.
+fn group_name(gid: libc::gid_t) -> Option<String> {
+    getent(
+        |grp, buf, result| unsafe {
+            libc::getgrgid_r(gid, grp, buf.as_mut_ptr(), buf.len(), result)
+        },
+        |grp: &libc::group| {
+            let name = unsafe { CStr::from_ptr(grp.gr_name) };
+            String::from_utf8_lossy(name.to_bytes()).into_owned()
+        },
+    )
+    .ok()
+    .flatten()
+}
+
+/// Lists and classifies the groups of the current process, from [`process_groups`].
+///
+/// The effective group comes first, and each group is only listed once. If `/etc/login.defs` is
+/// missing or unreadable, the groups are classified against [`DEFAULT_GID_RANGE`], like [`of_uid`]
+/// falls back to [`DEFAULT_RANGE`]; other errors, like invalid values, are still returned.
+pub fn groups() -> Result<Vec<Group>, Error> {
+    let range = match gid_range() {
+        Err(err) if (100..=102).contains(&err.code()) => DEFAULT_GID_RANGE,
+        range => range?,
+    };
+    let mut gids = process_groups();
+    let mut seen = Vec::with_capacity(gids.len());
+    gids.retain(|gid| {
+        let is_new = !seen.contains(gid);
+        seen.push(*gid);
+        is_new
+    });
+    Ok(gids
+        .into_iter()
+        .map(|gid| {
+            let name = group_name(gid);
+            let kind = classify_group(gid, name.as_deref(), &range);
+            Group { gid, name, kind }
+        })
+        .collect())
+}
+
+/// Detailed report of how [`UidRange`] was determined.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Report {
+    /// Effective user ID.
+    pub uid: libc::uid_t,
+
+    /// Name of the user, if it's in the user database.
+    pub name: Option<String>,
+
+    /// Path of `login.defs`, if it wasn't the live `/etc/login.defs`, like with [`report_of_in`].
+    ///
+    /// See [`Report::login_defs_path`] for the path either way.
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_sysroot_path))]
+    pub login_defs: Option<Box<Path>>,
+
+    /// `UID_MIN..=UID_MAX` range from `/etc/login.defs`, if it was consulted.
+    pub range: Option<RangeInclusive<libc::uid_t>>,
+
+    /// Where the values of the range came from, if it was consulted.
+    pub provenance: Option<Provenance>,
+
+    /// Home directory the result was guessed from, if `/etc/login.defs` was missing or unreadable.
+    ///
+    /// See [`guess_home`] for how the result is guessed; these guesses are low-confidence.
+    pub home: Option<String>,
+
+    /// Why `/etc/login.defs` couldn't be used, if the result was determined from other sources.
+    ///
+    /// In this case, either [`home`](Report::home) is set, or [`range`](Report::range) is
+    /// [`DEFAULT_RANGE`].
+    pub degraded: Option<Error>,
+
+    /// Resulting range.
+    pub result: UidRange,
+
+    /// Audit login UID of the calling process, from [`login_uid`], if this is the current user's
+    /// report and it's set.
+    ///
+    /// This is the user who originally logged in, which differs from [`uid`](Report::uid) after
+    /// commands like `su` and `sudo`.
+    pub login_uid: Option<libc::uid_t>,
+
+    /// Owner of the controlling terminal, from [`tty_owner`], if this is the current user's report
+    /// and there is one.
+    ///
+    /// Terminals stay owned by the user who logged in on them, so, if this differs from
+    /// [`uid`](Report::uid), the session probably switched users with `su` or `sudo -i`.
+    pub tty_owner: Option<libc::uid_t>,
+
+    /// Kind of session, from [`session`], if this is the current user's report and it was
+    /// detected.
+    pub session: Option<Session>,
+
+    /// First of the [`ADMIN_GROUPS`] the user is in, if they're an ordinary user.
+    ///
+    /// For the current user's report, this is checked against the groups of the process, from
+    /// [`process_groups`]; for other users, against the group database, from [`user_groups`].
+    /// Reports for alternate roots never have this, since their group databases aren't consulted.
+    pub admin_group: Option<String>,
+}
+impl Report {
+    /// Permissions corresponding to the result.
+    ///
+    /// Like [`with_groups`], this is [`Permissions::Operator`] for ordinary users in one of the
+    /// [`ADMIN_GROUPS`].
+    #[inline]
+    pub fn permissions(&self) -> Permissions {
+        match self.result {
+            UidRange::InRange if self.admin_group.is_some() => Permissions::Operator,
+            result => result.into(),
+        }
+    }
+
+    /// Path of the `login.defs` which was consulted, or would have been.
+    #[inline]
+    pub fn login_defs_path(&self) -> &Path {
+        login_defs_path(&self.login_defs)
+    }
+
+    /// Human-readable, single-line trace of how the result was determined.
+    pub fn explain(&self) -> impl fmt::Display + '_ {
+        Explain(self)
+    }
+}
+
+/// Return value of [`Report::explain`].
+struct Explain<'a>(&'a Report);
+impl fmt::Display for Explain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Report {
+            uid,
+            range,
+            provenance,
+            home,
+            degraded,
+            login_uid,
+            tty_owner,
+            admin_group,
+            ..
+        } = self.0;
+        match range {
+            None => match home {
+                Some(home) => write!(
+                    f,
+                    "uid {uid} has home directory {home}, guessed since {} (low confidence)",
+                    MissingOr(self.0)
+                )?,
+                None => write!(f, "uid {uid} is root")?,
+            },
+            Some(range) => {
+                let (min, max) = (range.start(), range.end());
+                match self.0.result {
+                    UidRange::AboveMax if uid <= max => write!(
+                        f,
+                        "uid {uid} is conventionally nobody, despite UID_MAX({max})"
+                    )?,
+                    UidRange::BelowMin => write!(f, "uid {uid} is below UID_MIN({min})")?,
+                    UidRange::AboveMax => write!(f, "uid {uid} is above UID_MAX({max})")?,
+                    _ => write!(f, "uid {uid} is within UID_MIN({min})..UID_MAX({max})")?,
+                }
+                match (degraded, provenance) {
+                    (Some(degraded), _) => {
+                        write!(f, " from the defaults, since {}", Degradation(degraded))?
+                    }
+                    (None, Some(Provenance { min, max })) => write!(
+                        f,
+                        " from {} (UID_MIN {min}; UID_MAX {max})",
+                        self.0.login_defs_path().display()
+                    )?,
+                    (None, None) => write!(f, " from {}", self.0.login_defs_path().display())?,
+                }
+            }
+        }
+        if let Some(login_uid) = login_uid.filter(|login_uid| login_uid != uid) {
+            write!(f, ", after logging in as uid {login_uid}")?;
+        }
+        if let Some(tty_owner) = tty_owner.filter(|tty_owner| tty_owner != uid) {
+            write!(f, ", on a terminal owned by uid {tty_owner}")?;
+        }
+        if let (UidRange::InRange, Some(group)) = (self.0.result, admin_group) {
+            write!(f, ", and can elevate as a member of {group}")?;
+        }
+        let level = self.0.permissions();
+        write!(f, " → {level} `{}`", level.be())
+    }
+}
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => writeln!(f, "user: {name}")?,
+            None => writeln!(f, "user: (unknown)")?,
+        }
+        writeln!(f, "uid: {}", self.uid)?;
+        if let Some(login_uid) = self.login_uid {
+            writeln!(f, "login uid: {login_uid}")?;
+        }
+        if let Some(tty_owner) = self.tty_owner {
+            writeln!(f, "tty owner: {tty_owner}")?;
+        }
+        if let Some(session) = self.session {
+            writeln!(f, "session: {session}")?;
+        }
+        if let Some(group) = &self.admin_group {
+            writeln!(f, "admin group: {group}")?;
+        }
+        match &self.range {
+            Some(range) => {
+                let from: &dyn fmt::Display = match &self.degraded {
+                    Some(_) => &"the defaults",
+                    None => &self.login_defs_path().display(),
+                };
+                writeln!(
+                    f,
+                    "range: UID_MIN {}, UID_MAX {} (from {from})",
+                    range.start(),
+                    range.end(),
+                )?
+            }
+            None if self.home.is_some() => writeln!(f, "range: unavailable ({})", MissingOr(self))?,
+            None => writeln!(f, "range: not consulted (uid 0)")?,
+        }
+        if let Some(home) = &self.home {
+            writeln!(f, "home: {home} (low-confidence guess)")?;
+        } else if let Some(degraded) = &self.degraded {
+            writeln!(f, "degraded: {}", Degradation(degraded))?;
+        }
+        let level = self.permissions();
+        write!(f, "level: {level} ({})", level.be())
+    }
+}
+
+/// Determine a detailed [`Report`] for the current user.
+///
+/// This performs the same logic as [`omst`], but additionally looks up the user's name and keeps
+/// track of the data that was consulted.
+pub fn report() -> Result<Report, Error> {
+    let uid = euid();
+    let mut report = Report {
+        login_uid: login_uid(),
+        tty_owner: tty_owner(),
+        session: session(),
+        ..report_in(Path::new("/"), uid, user_name(uid))?
+    };
+    if report.result == UidRange::InRange {
+        report.admin_group = admin_group(&process_groups()).map(str::to_owned);
+    }
+    Ok(report)
+}
+
+/// Audit login UID of the calling process, if it's set.
+///
+/// On Linux, this is read from `/proc/self/loginuid`, which is set by `pam_loginuid` when a user
+/// logs in and is inherited across `su`, `sudo`, and `setuid` programs. It's unset for processes
+/// which were never part of a login, like system services, and on other systems.
+pub fn login_uid() -> Option<libc::uid_t> {
+    #[cfg(target_os = "linux")]
+    {
+        let buf = fs::read("/proc/self/loginuid").ok()?;
+        // `(uid_t)-1` means that it's unset
+        atoi::<libc::uid_t>(buf.trim_ascii()).filter(|&uid| uid != libc::uid_t::MAX)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Owner of the controlling terminal, if there is one.
+///
+/// The terminal is whichever of standard input, output, or error is one first. Terminals are owned
+/// by the user who logged in on them, and `su` and `sudo -i` don't change that, so, an owner other
+/// than [`euid`] suggests that the session switched users. This is only a heuristic: for example,
+/// terminals opened by `sudo` with `use_pty` or by `tmux` are owned by whoever opened them.
+pub fn tty_owner() -> Option<libc::uid_t> {
+    (0..=2).find_map(|fd| {
+        // SAFETY: `isatty` is safe to call on any descriptor.
+        if unsafe { libc::isatty(fd) } != 1 {
+            return None;
+        }
+        let mut stat = MaybeUninit::<libc::stat>::uninit();
+        // SAFETY: `stat` is valid for writes, and is only read if the call succeeded.
+        if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } != 0 {
+            return None;
+        }
+        // SAFETY: the call succeeded, so the status was written.
+        Some(unsafe { stat.assume_init() }.st_uid)
+    })
+}
+
+/// Determine a detailed [`Report`] for an arbitrary user ID.
+pub fn report_of(uid: libc::uid_t) -> Result<Report, Error> {
+    let mut report = report_in(Path::new("/"), uid, user_name(uid))?;
+    if report.result == UidRange::InRange {
+        report.admin_group = user_admin_group(uid).map(str::to_owned);
+    }
+    Ok(report)
+}
+
+/// Determine a detailed [`Report`] for an arbitrary user ID, using `login.defs` in an alternate
+/// root.
+///
+/// Like with [`of_uid_in`], the name and the home directory used for guesses come from
+/// `etc/passwd` of the root.
+pub fn report_of_in(sysroot: &Path, uid: libc::uid_t) -> Result<Report, Error> {
+    report_in(sysroot, uid, passwd_in(sysroot, uid).map(|(name, _)| name))
+}
+
+/// Determine a detailed [`Report`] for the given user, without checking their groups.
+fn report_in(sysroot: &Path, uid: libc::uid_t, name: Option<String>) -> Result<Report, Error> {
+    let mut report = Report {
+        uid,
+        name,
+        login_defs: (sysroot != Path::new("/"))
+            .then(|| sysroot.join("etc/login.defs").into_boxed_path()),
+        range: None,
+        provenance: None,
+        home: None,
+        degraded: None,
+        result: UidRange::Zero,
+        login_uid: None,
+        tty_owner: None,
+        session: None,
+        admin_group: None,
+    };
+    if uid == 0 {
+        return Ok(report);
+    }
+    match load(sysroot, Duplicates::default(), Some(DEFAULT_RANGE)) {
+        Ok((range, provenance)) => {
+            report.result = classify(uid, &range);
+            report.range = Some(range);
+            report.provenance = Some(provenance);
+        }
+        Err(err) => {
+            let degraded = err.clone();
+            let (result, fallback) = guess_in(sysroot, uid, err)?;
+            match fallback {
+                Fallback::Home(home) => {
+                    report.home = Some(String::from_utf8_lossy(&home).into_owned());
+                }
+                Fallback::Defaults => report.range = Some(DEFAULT_RANGE),
+            }
+            report.degraded = Some(degraded);
+            report.result = result;
+        }
+    }
+    Ok(report)
+}
+
+/// Determine [`UidRange`] for an arbitrary user name.
+///
+/// This looks up the user's ID with [`user_id`], then classifies it with [`of_uid`].
+pub fn of_user(name: &str) -> Result<UidRange, Error> {
+    of_uid(user_id(name)?)
+}
+
+/// Determine a detailed [`Report`] for an arbitrary user name.
+pub fn report_of_user(name: &str) -> Result<Report, Error> {
+    report_of(user_id(name)?)
+}
+
+/// Looks up the effective user ID of a process.
+///
+/// On Linux, this is read from `/proc/PID/status`. On other systems, this falls back to the owner
+/// of `/proc/PID`, which requires `procfs` to be mounted.
+pub fn process_uid(pid: u32) -> Result<libc::uid_t, Error> {
+    let err = |error: io::Error| Error::Process {
+        pid,
+        error: error.into(),
+    };
+    #[cfg(target_os = "linux")]
+    {
+        let status = fs::read(format!("/proc/{pid}/status")).map_err(err)?;
+        status
+            .split(|b| *b == b'\n')
+            .find_map(|line| line.strip_prefix(b"Uid:"))
+            .and_then(|line| {
+                // fields are real, effective, saved, and filesystem UIDs
+                line.split(|b| b.is_ascii_whitespace())
+                    .filter(|field| !field.is_empty())
+                    .nth(1)
+                    .and_then(atoi::<libc::uid_t>)
+            })
+            .ok_or(Error::Process {
+                pid,
+                error: IoError::new(
+                    ErrorKind::InvalidData,
+                    "missing Uid field in process status",
+                ),
+            })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        fs::metadata(format!("/proc/{pid}"))
+            .map(|meta| meta.uid())
+            .map_err(err)
+    }
+}
+
+/// Determine [`UidRange`] for the owner of a process.
+///
+/// This looks up the process's effective user ID with [`process_uid`], then classifies it with
+/// [`of_uid`].
+pub fn of_pid(pid: u32) -> Result<UidRange, Error> {
+    of_uid(process_uid(pid)?)
+}
+
+/// Determine [`Permissions`] for a set of credentials, like those of a client connecting to a Unix
+/// socket.
+///
+/// Like with [`of_uid`], only the user ID decides the range; the group ID and supplementary groups
+/// are then checked with [`with_groups`], so that ordinary users in one of the [`ADMIN_GROUPS`] are
+/// [`Permissions::Operator`].
+pub fn of_credentials(
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+    groups: &[libc::gid_t],
+) -> Result<Permissions, Error> {
+    let mut all = Vec::with_capacity(groups.len() + 1);
+    all.push(gid);
+    all.extend_from_slice(groups);
+    Ok(with_groups(of_uid(uid)?, &all))
+}
+
+/// Looks up the effective user and group IDs of the peer of a connected Unix socket.
+///
+/// These are the IDs the peer had when it connected (or created the socket pair), read with
+/// `SO_PEERCRED` on Linux and Android, and with `getpeereid` on BSDs and Apple platforms. Other
+/// systems fail with [`ErrorKind::Unsupported`].
+pub fn peer_credentials<S: AsFd>(socket: S) -> Result<(libc::uid_t, libc::gid_t), Error> {
+    let fd = socket.as_fd().as_raw_fd();
+    let err = |error: io::Error| Error::Peer {
+        error: error.into(),
+    };
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let mut cred = MaybeUninit::<libc::ucred>::uninit();
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        // SAFETY: `cred` is valid for `len` bytes, and is only read if the call succeeded.
+        let ret = unsafe {
+            libc::getsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                cred.as_mut_ptr().cast(),
+                &mut len,
+            )
+        };
+        if ret != 0 {
+            return Err(err(io::Error::last_os_error()));
+        }
+        // SAFETY: the call succeeded, so the credentials were written.
+        let cred = unsafe { cred.assume_init() };
+        Ok((cred.uid, cred.gid))
+    }
+    #[cfg(any(
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd",
+    ))]
+    {
+        let (mut uid, mut gid) = (0, 0);
+        // SAFETY: both pointers are valid for writes.
+        if unsafe { libc::getpeereid(fd, &mut uid, &mut gid) } != 0 {
+            return Err(err(io::Error::last_os_error()));
+        }
+        Ok((uid, gid))
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd",
+    )))]
+    {
+        let _ = fd;
+        Err(err(io::Error::new(
+            ErrorKind::Unsupported,
+            "peer credentials are unavailable on this system",
+        )))
+    }
+}
+
+/// `SO_PEERGROUPS`, which `libc` doesn't export; it's the same on every architecture but SPARC.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SO_PEERGROUPS: libc::c_int = if cfg!(any(target_arch = "sparc", target_arch = "sparc64")) {
+    0x3d
+} else {
+    59
+};
+
+/// Looks up the supplementary groups of the peer of a connected Unix socket.
+///
+/// These are the groups the peer had when it connected (or created the socket pair), read with
+/// `SO_PEERGROUPS` on Linux and Android. Other systems, and kernels older than Linux 4.13, have no
+/// way to read them, so an empty list is returned instead, and only the group ID from
+/// [`peer_credentials`] is known.
+pub fn peer_groups<S: AsFd>(socket: S) -> Result<Vec<libc::gid_t>, Error> {
+    let fd = socket.as_fd().as_raw_fd();
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        const SIZE: usize = std::mem::size_of::<libc::gid_t>();
+        let mut groups: Vec<libc::gid_t> = vec![0; 16];
+        loop {
+            let mut len = (groups.len() * SIZE) as libc::socklen_t;
+            // SAFETY: `groups` is valid for `len` bytes.
+            let ret = unsafe {
+                libc::getsockopt(
+                    fd,
+                    libc::SOL_SOCKET,
+                    SO_PEERGROUPS,
+                    groups.as_mut_ptr().cast(),
+                    &mut len,
+                )
+            };
+            if ret == 0 {
+                groups.truncate(len as usize / SIZE);
+                return Ok(groups);
+            }
+
+            // if the buffer is too small, the kernel reports how large it needs to be
+            let error = io::Error::last_os_error();
+            match error.raw_os_error() {
+                Some(libc::ERANGE) if len as usize / SIZE > groups.len() => {
+                    groups.resize(len as usize / SIZE, 0);
+                }
+                Some(libc::ENOPROTOOPT) => return Ok(Vec::new()),
+                _ => {
+                    return Err(Error::Peer {
+                        error: error.into(),
+                    })
+                }
+            }
+        }
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let _ = fd;
+        Ok(Vec::new())
+    }
+}
+
+/// Determine [`Permissions`] for the peer of a connected Unix socket.
+///
+/// This looks up the peer's credentials with [`peer_credentials`] and [`peer_groups`], then
+/// classifies them with [`of_credentials`].
+pub fn of_peer<S: AsFd>(socket: S) -> Result<Permissions, Error> {
+    let fd = socket.as_fd();
+    let (uid, gid) = peer_credentials(fd)?;
+    of_credentials(uid, gid, &peer_groups(fd)?)
+}
+
+/// Determine [`UidRange`] for the owner of a file.
+///
+/// Owners of files on idmapped mounts are already translated by the kernel, and are classified
+/// like any other user. However, owners which can't be mapped at all, either because they fall
+/// outside the current user namespace (see `/proc/self/uid_map`) or because an NFS server couldn't
+/// map them to a local user, are reported by the kernel as the overflow UID. Rather than
+/// classifying these as [`UidRange::AboveMax`], we return [`Error::Unmapped`].
+pub fn of_path<P: AsRef<Path>>(path: P) -> Result<UidRange, Error> {
+    let path = path.as_ref();
+    let uid = fs::metadata(path)
+        .map_err(|error| Error::Stat {
+            path: path.into(),
+            error: error.into(),
+        })?
+        .uid();
+    if is_unmapped(path, uid) {
+        Err(Error::Unmapped { path: path.into() })
+    } else {
+        of_uid(uid)
+    }
+}
+
+/// Whether a file owner reported by the kernel is actually an unmapped owner.
+#[cfg(target_os = "linux")]
+fn is_unmapped(path: &Path, uid: libc::uid_t) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    const NFS_SUPER_MAGIC: u32 = 0x6969;
+
+    let overflow = fs::read("/proc/sys/kernel/overflowuid")
+        .ok()
+        .and_then(|buf| atoi::<libc::uid_t>(buf.trim_ascii()))
+        .unwrap_or(65534);
+    if uid != overflow {
+        return false;
+    }
+
+    // if the overflow UID isn't in our namespace, it can only come from an unmapped owner
+    if let Ok(map) = fs::read("/proc/self/uid_map") {
+        let mapped = map.split(|b| *b == b'\n').any(|line| {
+            let mut fields = line
+                .split(|b| b.is_ascii_whitespace())
+                .filter(|field| !field.is_empty())
+                .map(atoi::<u64>);
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(Some(start)), Some(Some(_)), Some(Some(len))) => {
+                    (start..start + len).contains(&u64::from(uid))
+                }
+                _ => false,
+            }
+        });
+        if !mapped {
+            return true;
+        }
+    }
+
+    // NFS reports owners it can't map (including squashed owners) as the overflow UID
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat = MaybeUninit::<libc::statfs>::uninit();
+    if unsafe { libc::statfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    unsafe { stat.assume_init() }.f_type as u32 == NFS_SUPER_MAGIC
+}
+
+/// Whether a file owner reported by the kernel is actually an unmapped owner.
+#[cfg(not(target_os = "linux"))]
+fn is_unmapped(_path: &Path, _uid: libc::uid_t) -> bool {
+    false
+}
+
+/// Detects the kind of session the current process is running in, if possible.
+///
+/// This is purely a heuristic, and the first of these which matches is returned:
+///
+/// 1. [`Session::Cron`], if `cron`, `crond`, or `anacron` is among the closest few ancestors of
+///    the process (only on Linux, via `/proc`)
+/// 2. [`Session::Ssh`], if `SSH_CONNECTION`, `SSH_CLIENT`, or `SSH_TTY` is set
+/// 3. [`Session::Container`], if [`container`] detects one
+/// 4. [`Session::Graphical`], if `WAYLAND_DISPLAY` or `DISPLAY` is set, or `XDG_SESSION_TYPE` is
+///    `x11` or `wayland`
+/// 5. [`Session::Console`], if there's a controlling terminal, as found by [`tty_owner`]
+pub fn session() -> Option<Session> {
+    let is_set = |var| std::env::var_os(var).is_some_and(|value| !value.is_empty());
+    if under_cron() {
+        Some(Session::Cron)
+    } else if ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .into_iter()
+        .any(is_set)
+    {
+        Some(Session::Ssh)
+    } else if container().is_some() {
+        Some(Session::Container)
+    } else if ["WAYLAND_DISPLAY", "DISPLAY"].into_iter().any(is_set)
+        || std::env::var_os("XDG_SESSION_TYPE")
+            .is_some_and(|kind| kind == "x11" || kind == "wayland")
+    {
+        Some(Session::Graphical)
+    } else if tty_owner().is_some() {
+        Some(Session::Console)
+    } else {
+        None
+    }
+}
+
+/// Whether one of the closest few ancestors of the process is `cron`.
+#[cfg(target_os = "linux")]
+fn under_cron() -> bool {
+    let mut pid = std::process::id();
+
+    // jobs are run with `sh -c`, which might not `exec` the command, so, we look a bit further
+    for _ in 0..4 {
+        let Ok(stat) = fs::read(format!("/proc/{pid}/stat")) else {
+            return false;
+        };
+
+        // the name is in parentheses, and can contain anything; it's followed by the state and
+        // the parent's ID
+        let ppid = stat
+            .iter()
+            .rposition(|b| *b == b')')
+            .and_then(|end| {
+                stat[end + 1..]
+                    .split(|b| *b == b' ')
+                    .filter(|field| !field.is_empty())
+                    .nth(1)
+            })
+            .and_then(atoi::<u32>);
+        let Some(ppid) = ppid.filter(|ppid| *ppid > 1) else {
+            return false;
+        };
+        let comm = fs::read(format!("/proc/{ppid}/comm")).unwrap_or_default();
+        if matches!(comm.trim_ascii(), b"cron" | b"crond" | b"anacron") {
+            return true;
+        }
+        pid = ppid;
+    }
+    false
+}
+
+/// Whether one of the closest few ancestors of the process is `cron`.
+#[cfg(not(target_os = "linux"))]
+fn under_cron() -> bool {
+    false
+}
+
+/// Detects whether we're running inside a container or chroot, returning its kind if so.
+///
+/// This is purely a heuristic: it checks for the marker files left by Docker (`/.dockerenv`) and
+/// Podman (`/run/.containerenv`), the `container` variable set by systemd-nspawn and LXC, and on
+/// Linux, the cgroup of PID 1 and whether our root differs from its root. The last check requires
+/// access to `/proc/1/root`, and so, chroots are usually only detected by root.
+pub fn container() -> Option<String> {
+    if Path::new("/.dockerenv").exists() {
+        return Some("docker".to_owned());
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_owned());
+    }
+    if let Some(kind) = std::env::var_os("container").filter(|kind| !kind.is_empty()) {
+        return Some(kind.to_string_lossy().into_owned());
+    }
+    proc_container()
+}
+
+/// Detects containers and chroots via `/proc`.
+#[cfg(target_os = "linux")]
+fn proc_container() -> Option<String> {
+    if let Ok(cgroup) = fs::read_to_string("/proc/1/cgroup") {
+        for kind in ["docker", "kubepods", "containerd", "lxc"] {
+            if cgroup.contains(kind) {
+                return Some(kind.to_owned());
+            }
+        }
+    }
+    let ours = fs::metadata("/").ok()?;
+    let init = fs::metadata("/proc/1/root").ok()?;
+    (ours.dev() != init.dev() || ours.ino() != init.ino()).then(|| "chroot".to_owned())
+}
+
+/// Detects containers and chroots via `/proc`.
+#[cfg(not(target_os = "linux"))]
+fn proc_container() -> Option<String> {
+    None
+}
+
+#[test]
+#[cfg(feature = "arbitrary")]
+fn arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    // deterministic, but varied enough to reach every variant
+    let data: Vec<u8> = (0..8192u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    let mut u = Unstructured::new(&data);
+    while let (Ok(value), Ok(err), Ok(report)) = (
+        Value::arbitrary(&mut u),
+        Error::arbitrary(&mut u),
+        Report::arbitrary(&mut u),
+    ) {
+        assert!(value.as_bytes().len() <= Value::CAPACITY);
+        assert!(!err.to_string().is_empty());
+        assert!((100..=150).contains(&err.code()));
+        assert!(!report.explain().to_string().is_empty());
+        if u.is_empty() {
+            break;
+        }
+    }
+}
+
+#[test]
+fn clone_eq() {
+    let err = parse(b"UID_MIN x\nUID_MAX 900\n", Duplicates::default(), None).unwrap_err();
+    assert_eq!(err.clone(), err);
+    assert_eq!(
+        err,
+        Error::InvalidDef {
+            def: Def::Min,
+            problem: Problem::Invalid {
+                data: Value::new(b"x")
+            },
+            path: None,
+        }
+    );
+    let stat = of_path("/nonexistent/omst").unwrap_err();
+    assert_eq!(
+        stat,
+        Error::Stat {
+            path: Path::new("/nonexistent/omst").into(),
+            error: IoError::from_raw_os_error(libc::ENOENT),
+        }
+    );
+    assert_ne!(
+        stat,
+        Error::Stat {
+            path: Path::new("/nonexistent/omst").into(),
+            error: IoError::from_raw_os_error(libc::EACCES),
+        }
+    );
+}
+
+#[test]
+fn codes() {
+    let open = |kind| Error::LoginDefs {
+        operation: Operation::Open,
+        error: IoError::from(kind),
+        path: None,
+    };
+    assert_eq!(open(ErrorKind::NotFound).code(), 100);
+    assert_eq!(open(ErrorKind::PermissionDenied).code(), 101);
+    assert_eq!(
+        Error::InvalidDef {
+            def: Def::Min,
+            problem: Problem::Invalid {
+                data: Value::new(b"x")
+            },
+            path: None,
+        }
+        .code(),
+        112
+    );
+}
+
+#[test]
+fn sysroot() {
+    let root = std::env::temp_dir().join(format!("omst-sysroot-{}", std::process::id()));
+    fs::create_dir_all(root.join("etc")).unwrap();
+    fs::write(root.join("etc/login.defs"), "UID_MIN 500\nUID_MAX 900\n").unwrap();
+    let range = uid_range_in(&root);
+    let report = report_of_in(&root, 700);
+    fs::write(root.join("etc/login.defs"), "UID_MIN 500\nUID_MAX x\n").unwrap();
+    let err = uid_range_in(&root).unwrap_err();
+    fs::remove_dir_all(&root).unwrap();
+    assert_eq!(range.unwrap(), 500..=900);
+
+    // errors and reports name the file that was read, instead of the live one
+    let path = root.join("etc/login.defs");
+    assert_eq!(
+        report.unwrap().explain().to_string(),
+        format!(
+            "uid 700 is within UID_MIN(500)..UID_MAX(900) from {} (UID_MIN on line 1; UID_MAX on \
+             line 2) → user `$`",
+            path.display()
+        )
+    );
+    assert_eq!(err.code(), 112);
+    assert_eq!(err.path(), Some(&*path));
+    assert_eq!(
+        err.to_string(),
+        format!("UID_MAX in {} was not a valid UID (x)", path.display())
+    );
+}
+
+#[test]
+fn duplicates() {
+    let defs = b"UID_MIN 500\nUID_MAX 900\nUID_MIN 600\nUID_MAX x\n";
+    assert_eq!(
+        parse(&defs[..], Duplicates::First, None).unwrap(),
+        500..=900
+    );
+    assert_eq!(
+        parse(&defs[..], Duplicates::Last, None).unwrap_err().code(),
+        112
+    );
+    let defs = b"UID_MIN 500\nUID_MAX 900\nUID_MIN 600\n";
+    assert_eq!(parse(&defs[..], Duplicates::Last, None).unwrap(), 600..=900);
+}
+
+#[test]
+fn shadow_duplicates() {
+    // like `getdef`, only the definition that's used is validated
+    let cases: &[(&[u8], Option<u16>)] = &[
+        (b"UID_MIN x\nUID_MIN 500\nUID_MAX 900\n", None),
+        (b"UID_MIN\t\"\"\nUID_MIN 500\nUID_MAX 900\n", None),
+        (b"UID_MIN 99999999999\nUID_MIN 500\nUID_MAX 900\n", None),
+        (b"UID_MIN 500\nUID_MAX 900\nUID_MIN x\n", Some(112)),
+        (b"UID_MIN 500\nUID_MAX 900\nUID_MAX \"\"\n", Some(111)),
+        // comments and names without values aren't definitions
+        (b"UID_MIN 500\nUID_MAX 900\n#UID_MIN x\nUID_MIN\n", None),
+    ];
+    for (defs, code) in cases {
+        let result = parse(defs, Duplicates::default(), None);
+        assert_eq!(result.as_ref().err().map(Error::code), *code, "{result:?}");
+        if code.is_none() {
+            assert_eq!(result.unwrap(), 500..=900);
+        }
+    }
+
+    let defs = b"UID_MIN 100\nUID_MAX 900\n# UID_MIN 200\nUID_MIN 300\nUID_MIN 500\n";
+    assert_eq!(
+        parse_traced(defs, Duplicates::Last, None).unwrap(),
+        (
+            500..=900,
+            Provenance {
+                min: Origin::Line {
+                    line: 5,
+                    ignored: 2
+                },
+                max: Origin::Line {
+                    line: 2,
+                    ignored: 0
+                },
+            }
+        )
+    );
+    assert_eq!(
+        parse_traced(defs, Duplicates::First, Some(DEFAULT_RANGE)).unwrap(),
+        (
+            100..=900,
+            Provenance {
+                min: Origin::Line {
+                    line: 1,
+                    ignored: 0
+                },
+                max: Origin::Line {
+                    line: 2,
+                    ignored: 0
+                },
+            }
+        )
+    );
+
+    let root = std::env::temp_dir().join(format!("omst-duplicates-{}", std::process::id()));
+    fs::create_dir_all(root.join("etc")).unwrap();
+    fs::write(root.join("etc/login.defs"), defs).unwrap();
+    let report = report_of_in(&root, 700);
+    fs::remove_dir_all(&root).unwrap();
+    assert_eq!(
+        report.unwrap().explain().to_string(),
+        format!(
+            "uid 700 is within UID_MIN(500)..UID_MAX(900) from {} (UID_MIN on line 5, ignoring 2 \
+             other definitions; UID_MAX on line 2) → user `$`",
+            root.join("etc/login.defs").display()
+        )
+    );
+}
+
+#[test]
+fn long_lines() {
+    let mut defs = format!("# {}\n", "x".repeat(LINE_MAX - 2)).into_bytes();
+    defs.extend_from_slice(b"UID_MIN 500\nUID_MAX 900");
+    assert_eq!(parse(&defs, Duplicates::First, None).unwrap(), 500..=900);
+
+    let defs = format!("UID_MIN 500\n# {}\nUID_MAX 900", "x".repeat(LINE_MAX));
+    let err = parse(defs.as_bytes(), Duplicates::First, None).unwrap_err();
+    assert_eq!(err.code(), 104);
+    assert_eq!(
+        err.to_string(),
+        "line 2 of /etc/login.defs is longer than 1024 bytes"
+    );
+
+    let defs = format!("UID_MIN 9{}\nUID_MAX 900\n", "x".repeat(100));
+    let err = parse(defs.as_bytes(), Duplicates::First, None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "UID_MIN in /etc/login.defs was not a valid UID (9{}...)",
+            "x".repeat(31)
+        )
+    );
+}
+
+#[test]
+fn root_owns_root() {
+    assert_eq!(of_path("/").unwrap(), UidRange::Zero);
+}
+
+#[test]
+fn classify_range() {
+    let range = 1000..=60000;
+    assert_eq!(classify(0, &range), UidRange::Zero);
+    assert_eq!(classify(999, &range), UidRange::BelowMin);
+    assert_eq!(classify(1000, &range), UidRange::InRange);
+    assert_eq!(classify(60000, &range), UidRange::InRange);
+    assert_eq!(classify(60001, &range), UidRange::AboveMax);
+}
+
+#[test]
+fn raw_euid() {
+    assert_eq!(euid(), unsafe { libc::geteuid() });
+}
+
+#[test]
+fn own_process() {
+    let uid = unsafe { libc::geteuid() };
+    assert_eq!(process_uid(std::process::id()).unwrap(), uid);
+}
+
+#[test]
+fn socket_peer() {
+    let (ours, _theirs) = std::os::unix::net::UnixStream::pair().unwrap();
+    let ids = unsafe { (libc::geteuid(), libc::getegid()) };
+    assert_eq!(peer_credentials(&ours).unwrap(), ids);
+    assert_eq!(of_peer(&ours).unwrap(), permissions().unwrap());
+    let process = process_groups();
+    assert!(peer_groups(&ours)
+        .unwrap()
+        .iter()
+        .all(|gid| process.contains(gid)));
+
+    let file = File::open("/etc/passwd").unwrap();
+    assert_eq!(of_peer(&file).unwrap_err().code(), 150);
+}
+
+#[test]
+fn explain() {
+    let report = Report {
+        uid: 1000,
+        name: None,
+        login_defs: None,
+        range: Some(1000..=60000),
+        provenance: None,
+        home: None,
+        degraded: None,
+        result: UidRange::InRange,
+        login_uid: None,
+        tty_owner: None,
+        session: None,
+        admin_group: None,
+    };
+    assert_eq!(
+        report.explain().to_string(),
+        "uid 1000 is within UID_MIN(1000)..UID_MAX(60000) from /etc/login.defs → user `$`"
+    );
+
+    let report = Report {
+        uid: 0,
+        range: None,
+        result: UidRange::Zero,
+        login_uid: Some(1000),
+        ..report
+    };
+    assert_eq!(
+        report.explain().to_string(),
+        "uid 0 is root, after logging in as uid 1000 → absolute `#`"
+    );
+
+    let report = Report {
+        login_uid: None,
+        tty_owner: Some(1000),
+        ..report
+    };
+    assert_eq!(
+        report.explain().to_string(),
+        "uid 0 is root, on a terminal owned by uid 1000 → absolute `#`"
+    );
+
+    let report = Report {
+        admin_group: Some("wheel".into()),
+        ..report
+    };
+    assert_eq!(report.permissions(), Permissions::Absolute);
+
+    let report = Report {
+        uid: 1000,
+        range: Some(1000..=60000),
+        result: UidRange::InRange,
+        tty_owner: None,
+        ..report
+    };
+    assert_eq!(
+        report.explain().to_string(),
+        "uid 1000 is within UID_MIN(1000)..UID_MAX(60000) from /etc/login.defs, and can elevate \
+         as a member of wheel → operator `&`"
+    );
+}
+
+#[test]
+fn admin_groups() {
+    let groups = process_groups();
+    assert!(groups.contains(&unsafe { libc::getegid() }));
+    assert_eq!(with_groups(UidRange::InRange, &[]), Permissions::User);
+    assert_eq!(with_groups(UidRange::Zero, &groups), Permissions::Absolute);
+    assert_eq!(
+        permissions().unwrap(),
+        with_groups(omst().unwrap(), &groups)
+    );
+
+    // only ordinary users can elevate, so, nobody else has an admin group
+    let report = report().unwrap();
+    assert_eq!(report.permissions(), permissions().unwrap());
+    if report.result != UidRange::InRange {
+        assert_eq!(report.admin_group, None);
+    }
+}
+
+#[test]
+fn users_include_root() {
+    assert!(users().iter().any(|user| user.uid == 0));
+}
+
+#[test]
+fn defaults() {
+    let defs = b"UID_MIN 500\n";
+    assert_eq!(
+        parse(defs, Duplicates::First, Some(DEFAULT_RANGE)).unwrap(),
+        500..=60000
+    );
+    assert_eq!(
+        parse(b"", Duplicates::First, Some(DEFAULT_RANGE)).unwrap(),
+        DEFAULT_RANGE
+    );
+    assert_eq!(
+        parse(defs, Duplicates::First, None).unwrap_err().code(),
+        110
+    );
+}
+
+#[test]
+fn numeric() {
+    assert_eq!(parse_uid(b"1000"), Some(1000));
+    assert_eq!(parse_uid(b"+1000"), Some(1000));
+    assert_eq!(parse_uid(b"0"), Some(0));
+    assert_eq!(parse_uid(b"01750"), Some(1000));
+    assert_eq!(parse_uid(b"0x3e8"), Some(1000));
+    assert_eq!(parse_uid(b"0XEA60"), Some(60000));
+    assert_eq!(parse_uid(b"0x"), None);
+    assert_eq!(parse_uid(b"08"), None);
+    assert_eq!(parse_uid(b"1000a"), None);
+    assert_eq!(parse_uid(b"99999999999"), Some(99999999999));
+    assert_eq!(parse_uid(b"999999999999999999999999"), Some(u64::MAX));
+
+    let defs = b"UID_MIN 0x3e8\nUID_MAX 0165140\n";
+    assert_eq!(parse(defs, Duplicates::First, None).unwrap(), 1000..=60000);
+}
+
+#[test]
+fn conformance() {
+    // each file is handled the same way by `getdef` in shadow-utils, other than errors, after which
+    // shadow-utils falls back to the defaults; files either define 500..=900 or fail with a code
+    let cases: &[(&[u8], Option<u16>)] = &[
+        (b"UID_MIN 500\nUID_MAX 900\n", None),
+        (b"  \tUID_MIN\t\t500  \nUID_MAX 900\t\x0b\r\n", None),
+        (b"UID_MIN \"500\"\nUID_MAX \"900\n", None),
+        (b"UID_MIN 500\" ordinary users\nUID_MAX 900\n", None),
+        (b"# UID_MIN 100\nUID_MIN 500\nUID_MAX 900\n", None),
+        (b" \t#UID_MIN 100\nUID_MIN 500\nUID_MAX 900\n", None),
+        (b"UID_MIN#100\nUID_MIN 500\nUID_MAX 900\n", None),
+        (b"UID_MIN\nUID_MIN 500\nUID_MAX 900\n", None),
+        (b"UID_MIN 500 600\nUID_MAX 900\n", Some(112)),
+        (b"UID_MIN \"\"\nUID_MAX 900\n", Some(111)),
+        (b"uid_min 500\nUID_MAX 900\n", Some(110)),
+        (b"UID_MAX 900\n", Some(110)),
+    ];
+    for (defs, expected) in cases {
+        let result = parse(defs, Duplicates::First, None);
+        let defs = String::from_utf8_lossy(defs);
+        match expected {
+            None => assert_eq!(result.unwrap(), 500..=900, "{defs}"),
+            Some(code) => assert_eq!(result.unwrap_err().code(), *code, "{defs}"),
+        }
+    }
+}
+
+#[test]
+fn trailing_comments() {
+    // unlike in `getdef`, a comment after a value doesn't make it invalid
+    let cases: &[(&[u8], Option<u16>)] = &[
+        (b"UID_MIN 500 # ordinary users\nUID_MAX 900\n", None),
+        (b"UID_MIN 500\t#ordinary users\nUID_MAX 900 #\n", None),
+        (b"UID_MIN \"500\" # ordinary users\nUID_MAX 900\n", None),
+        (
+            b"UID_MIN 500 600 # ordinary users\nUID_MAX 900\n",
+            Some(112),
+        ),
+        (b"UID_MIN 500# ordinary users\nUID_MAX 900\n", Some(112)),
+        (b"UID_MIN # ordinary users\nUID_MAX 900\n", Some(111)),
+    ];
+    for (defs, expected) in cases {
+        let result = parse(defs, Duplicates::First, None);
+        let defs = String::from_utf8_lossy(defs);
+        match expected {
+            None => assert_eq!(result.unwrap(), 500..=900, "{defs}"),
+            Some(code) => assert_eq!(result.unwrap_err().code(), *code, "{defs}"),
+        }
+    }
+}
+
+#[test]
+fn line_endings() {
+    let defs = b"\xef\xbb\xbfUID_MIN 500\r\nUID_MAX\t900 \t\r\n";
+    assert_eq!(parse(defs, Duplicates::Last, None).unwrap(), 500..=900);
+    let defs = b"# comment\r\n\r\n\tUID_MIN \t \"500\"\r\nUID_MAX 900\r";
+    assert_eq!(parse(defs, Duplicates::Last, None).unwrap(), 500..=900);
+
+    let mut defs = format!("# {}\r\n", "x".repeat(LINE_MAX - 2)).into_bytes();
+    defs.extend_from_slice(b"UID_MIN 500\r\nUID_MAX 900\r\n");
+    assert_eq!(parse(&defs, Duplicates::First, None).unwrap(), 500..=900);
+}
+
+#[test]
+fn unreadable() {
+    // nobody has this UID, so nothing can be guessed from its home directory
+    let uid = 3_999_999_999;
+    let denied = || Error::LoginDefs {
+        operation: Operation::Open,
+        error: IoError::from(ErrorKind::PermissionDenied),
+        path: None,
+    };
+    assert_eq!(
+        Degradation(&denied()).to_string(),
+        "/etc/login.defs is unreadable (permission denied)"
+    );
+    assert!(matches!(
+        guess(uid, denied()),
+        Ok((UidRange::AboveMax, Fallback::Defaults))
+    ));
+    assert!(matches!(
+        guess(1500, denied()),
+        Ok((UidRange::InRange, Fallback::Defaults))
+    ));
+    let missing = Error::LoginDefs {
+        operation: Operation::Open,
+        error: IoError::from(ErrorKind::NotFound),
+        path: None,
+    };
+    assert_eq!(guess(uid, missing).unwrap_err().code(), 100);
+    assert_eq!(
+        guess(uid, Error::FileTooLarge { path: None })
+            .unwrap_err()
+            .code(),
+        103
+    );
+
+    let report = Report {
+        uid: 1500,
+        name: None,
+        login_defs: None,
+        range: Some(DEFAULT_RANGE),
+        provenance: None,
+        home: None,
+        degraded: Some(denied()),
+        result: UidRange::InRange,
+        login_uid: None,
+        tty_owner: None,
+        session: None,
+        admin_group: None,
+    };
+    assert_eq!(
+        report.explain().to_string(),
+        "uid 1500 is within UID_MIN(1000)..UID_MAX(60000) from the defaults, since \
+         /etc/login.defs is unreadable (permission denied) → user `$`"
+    );
+}
+
+#[test]
+fn home_guesses() {
+    assert_eq!(guess_home(b"/home/user"), Some(UidRange::InRange));
+    assert_eq!(guess_home(b"/var/lib/colord"), Some(UidRange::BelowMin));
+    assert_eq!(guess_home(b"/"), Some(UidRange::BelowMin));
+    assert_eq!(guess_home(b""), Some(UidRange::BelowMin));
+    assert_eq!(guess_home(b"/nonexistent"), Some(UidRange::AboveMax));
+    assert_eq!(guess_home(b"/root"), None);
+    assert_eq!(guess_home(b"/homework"), None);
+
+    let report = Report {
+        uid: 1000,
+        name: None,
+        login_defs: None,
+        range: None,
+        provenance: None,
+        home: Some("/home/user".to_owned()),
+        degraded: None,
+        result: UidRange::InRange,
+        login_uid: None,
+        tty_owner: None,
+        session: None,
+        admin_group: None,
+    };
+    assert_eq!(
+        report.explain().to_string(),
+        "uid 1000 has home directory /home/user, guessed since /etc/login.defs is missing (low \
+         confidence) → user `$`"
+    );
+}
+
+#[cfg(feature = "miette")]
+#[test]
+fn diagnostics() {
+    use miette::Diagnostic;
+
+    let err = Error::FileTooLarge { path: None };
+    assert_eq!(Diagnostic::code(&err).unwrap().to_string(), "omst::103");
+    assert!(err.help().is_some());
+}
+
+#[test]
+fn out_of_range() {
+    let defs = b"UID_MIN 1000\nUID_MAX 99999999999\n";
+    assert_eq!(
+        parse(defs, Duplicates::First, None).unwrap(),
+        1000..=libc::uid_t::MAX
+    );
+    let defs = b"UID_MIN 99999999999\nUID_MAX 60000\n";
+    assert_eq!(
+        parse(defs, Duplicates::First, None).unwrap_err().code(),
+        113
+    );
+}
+
+#[test]
+fn nobody() {
+    let range = 1000..=100000;
+    assert_eq!(classify(65534, &range), UidRange::AboveMax);
+    assert_eq!(classify(libc::uid_t::MAX - 1, &range), UidRange::AboveMax);
+    assert_eq!(classify_by_range(65534, &range), UidRange::InRange);
+    assert_eq!(classify(65533, &range), UidRange::InRange);
+
+    let report = Report {
+        uid: 65534,
+        name: None,
+        login_defs: None,
+        range: Some(range),
+        provenance: None,
+        home: None,
+        degraded: None,
+        result: UidRange::AboveMax,
+        login_uid: None,
+        tty_owner: None,
+        session: None,
+        admin_group: None,
+    };
+    assert_eq!(
+        report.explain().to_string(),
+        "uid 65534 is conventionally nobody, despite UID_MAX(100000) from /etc/login.defs → guest \
+         `%`"
+    );
+}
+
+/// Classifies a range of UIDs, and the users in the tree's `etc/passwd`, against each `/etc` tree in
+/// `fixtures/shadow`, comparing the explanations to the tree's `snapshot` file.
+///
+/// Set `OMST_UPDATE_SNAPSHOTS` to rewrite the snapshots instead, and review the diff.
+#[test]
+fn fixtures() {
+    const UIDS: &[libc::uid_t] = &[
+        0,
+        1,
+        99,
+        201,
+        499,
+        500,
+        999,
+        1000,
+        29999,
+        30000,
+        60000,
+        60001,
+        65534,
+        libc::uid_t::MAX - 1,
+    ];
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/shadow");
+    let mut roots: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    roots.sort();
+    assert!(!roots.is_empty());
+
+    let update = std::env::var_os("OMST_UPDATE_SNAPSHOTS").is_some();
+    let mut changed = Vec::new();
+    for root in roots {
+        // users in the tree are also classified, since their homes are used for guesses
+        let mut uids = UIDS.to_vec();
+        if let Ok(passwd) = fs::read_to_string(root.join("etc/passwd")) {
+            uids.extend(
+                passwd
+                    .lines()
+                    .filter_map(|line| line.split(':').nth(2)?.parse::<libc::uid_t>().ok()),
+            );
+        }
+        uids.sort_unstable();
+        uids.dedup();
+
+        let mut actual = String::new();
+        for uid in uids {
+            match report_of_in(&root, uid) {
+                Ok(report) => actual.push_str(&report.explain().to_string()),
+                Err(err) => actual.push_str(&format!("uid {uid}: error {}: {err}", err.code())),
+            }
+            actual.push('\n');
+        }
+        // paths in the fixture are shown relative to it, so that the snapshots don't depend on
+        // where the repository is checked out
+        let actual = actual.replace(&*root.to_string_lossy(), "$ROOT");
+
+        let snapshot = root.join("snapshot");
+        if update {
+            fs::write(&snapshot, actual).unwrap();
+        } else if fs::read_to_string(&snapshot).ok().as_deref() != Some(&*actual) {
+            changed.push(root.file_name().unwrap().to_string_lossy().into_owned());
+        }
+    }
+    assert!(
+        changed.is_empty(),
+        "snapshots changed for {changed:?}; rerun with OMST_UPDATE_SNAPSHOTS=1 and review the diff"
+    );
+}
+
+#[test]
+fn group_kinds() {
+    let defs = b"UID_MIN 2000\nGID_MIN 500\nGID_MAX 0x100000000\n";
+    let (range, _) = parse_defs(
+        defs,
+        None,
+        [Def::GidMin, Def::GidMax],
+        Duplicates::Last,
+        None,
+    )
+    .unwrap();
+    assert_eq!(range, 500..=libc::gid_t::MAX);
+    let err = parse_defs(
+        b"GID_MIN\t\"\"\n",
+        None,
+        [Def::GidMin, Def::GidMax],
+        Duplicates::Last,
+        None,
+    );
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "GID_MIN in /etc/login.defs was empty"
+    );
+    let err = parse_defs(
+        b"GID_MIN 99999999999\nGID_MAX x\n",
+        None,
+        [Def::GidMin, Def::GidMax],
+        Duplicates::Last,
+        None,
+    );
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        format!(
+            "GID_MIN in /etc/login.defs was larger than the largest GID, {} (99999999999)",
+            libc::uid_t::MAX
+        )
+    );
+    let err = parse_defs(
+        b"GID_MIN 500\nGID_MAX x\n",
+        None,
+        [Def::GidMin, Def::GidMax],
+        Duplicates::Last,
+        None,
+    );
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        "GID_MAX in /etc/login.defs was not a valid GID (x)"
+    );
+
+    assert_eq!(
+        classify_group(0, Some("root"), &DEFAULT_GID_RANGE),
+        GroupKind::Root
+    );
+    assert_eq!(
+        classify_group(27, None, &DEFAULT_GID_RANGE),
+        GroupKind::System
+    );
+    assert_eq!(
+        classify_group(65534, Some("nogroup"), &DEFAULT_GID_RANGE),
+        GroupKind::AboveMax
+    );
+    assert_eq!(
+        classify_group(1000, None, &DEFAULT_GID_RANGE),
+        GroupKind::Shared
+    );
+
+    let groups = groups().unwrap();
+    assert_eq!(groups[0].gid, unsafe { libc::getegid() });
+}
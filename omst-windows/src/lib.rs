@@ -0,0 +1,1192 @@
+//! Implementation for Windows API.
+//!
+//! This is the `omst::winapi` module; most users want the `omst` crate instead, which picks the
+//! backend for the current platform.
+#![cfg(windows)]
+#![warn(unsafe_op_in_unsafe_fn)]
+use omst_core::{Account, BackendInfo, Cost, IoError, Permissions, Session};
+use std::error::Error as StdError;
+use std::ffi::c_void;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::mem;
+use std::process::abort;
+use std::ptr;
+use std::slice;
+use std::sync::OnceLock;
+use windows_sys::core::{PCWSTR, PWSTR};
+use windows_sys::Win32::Foundation::{
+    CloseHandle, LocalFree, ERROR_INSUFFICIENT_BUFFER, ERROR_MORE_DATA, ERROR_NO_TOKEN, HANDLE,
+};
+use windows_sys::Win32::NetworkManagement::NetManagement::{
+    NERR_UserNotFound, FILTER_NORMAL_ACCOUNT, MAX_PREFERRED_LENGTH, UNLEN, USER_INFO_1,
+    USER_PRIV_ADMIN, USER_PRIV_GUEST, USER_PRIV_USER,
+};
+use windows_sys::Win32::Security::Authorization::ConvertSidToStringSidW;
+use windows_sys::Win32::Security::{
+    CreateWellKnownSid, EqualSid, GetSidSubAuthority, GetSidSubAuthorityCount, GetTokenInformation,
+    IsWellKnownSid, LookupAccountNameW, TokenElevation, TokenElevationType, TokenElevationTypeFull,
+    TokenElevationTypeLimited, TokenGroups, TokenIntegrityLevel, TokenUser,
+    WinBuiltinAdministratorsSid, WinBuiltinGuestsSid, WinLocalServiceSid, WinLocalSystemSid,
+    WinNetworkServiceSid, PSID, SECURITY_MAX_SID_SIZE, SID_NAME_USE, TOKEN_ELEVATION,
+    TOKEN_ELEVATION_TYPE, TOKEN_GROUPS, TOKEN_INFORMATION_CLASS, TOKEN_MANDATORY_LABEL,
+    TOKEN_QUERY, TOKEN_USER, WELL_KNOWN_SID_TYPE,
+};
+use windows_sys::Win32::System::LibraryLoader::{
+    GetProcAddress, LoadLibraryExW, LOAD_LIBRARY_SEARCH_SYSTEM32,
+};
+use windows_sys::Win32::System::SystemServices::{
+    SECURITY_MANDATORY_HIGH_RID, SECURITY_MANDATORY_LOW_RID, SECURITY_MANDATORY_MEDIUM_PLUS_RID,
+    SECURITY_MANDATORY_MEDIUM_RID, SECURITY_MANDATORY_SYSTEM_RID, SECURITY_MANDATORY_UNTRUSTED_RID,
+};
+use windows_sys::Win32::System::Threading::{
+    GetCurrentProcess, GetCurrentThread, OpenProcessToken, OpenThreadToken,
+};
+use windows_sys::Win32::System::WindowsProgramming::GetUserNameW;
+
+/// Information about this implementation.
+pub const BACKEND: BackendInfo = BackendInfo::new(
+    "winapi",
+    &["GetUserNameW", "NetUserGetInfo"],
+    Cost::Services,
+);
+
+/// Windows user privileges.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum Priv {
+    /// Guest user privileges.
+    Guest = b'%',
+
+    /// Regular user privileges.
+    User = b'$',
+
+    /// Built-in service account privileges, for the LocalService and NetworkService accounts.
+    ///
+    /// These accounts don't have a `USER_INFO`, and are detected by their SIDs instead.
+    System = b'@',
+
+    /// Administrator privileges.
+    ///
+    /// This includes the LocalSystem account (`SYSTEM`), which has every privilege an administrator
+    /// has, and more.
+    Admin = b'#',
+}
+impl From<Priv> for Permissions {
+    #[inline]
+    fn from(r#priv: Priv) -> Permissions {
+        match r#priv {
+            Priv::Guest => Permissions::Guest,
+            Priv::User => Permissions::User,
+            Priv::System => Permissions::System,
+            Priv::Admin => Permissions::Absolute,
+        }
+    }
+}
+
+/// Operation done when getting user privileges.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Operation {
+    /// `GetUserNameW`.
+    GetUserName,
+
+    /// `NetNetUserGetInfo`.
+    NetUserGetInfo,
+
+    /// `NetUserEnum`.
+    NetUserEnum,
+
+    /// `OpenProcessToken`.
+    OpenProcessToken,
+
+    /// `OpenThreadToken`.
+    OpenThreadToken,
+
+    /// `GetTokenInformation`.
+    GetTokenInformation,
+
+    /// `LoadLibraryExW` or `GetProcAddress`, for `netapi32.dll`.
+    LoadNetApi,
+}
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Operation::GetUserName => "get username",
+            Operation::NetUserGetInfo => "get user info",
+            Operation::NetUserEnum => "enumerate users",
+            Operation::OpenProcessToken => "open process token",
+            Operation::OpenThreadToken => "open thread token",
+            Operation::GetTokenInformation => "get token information",
+            Operation::LoadNetApi => "load netapi32.dll",
+        })
+    }
+}
+
+/// Error that can occur when getting permissions from the Windows API.
+///
+/// I/O errors are stored as an [`IoError`], so that errors can be cloned and compared.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Error {
+    /// Error getting privileges.
+    GetPriv {
+        /// Operation that failed.
+        operation: Operation,
+
+        /// Error that occurred.
+        error: IoError,
+    },
+
+    /// Invalid user privileges.
+    InvalidPriv { data: u32 },
+}
+impl StdError for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::GetPriv { error, .. } => Some(error),
+            Error::InvalidPriv { .. } => None,
+        }
+    }
+}
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    /// Serializes the error as a struct with `kind`, `message`, `os_code`, and `path` fields.
+    ///
+    /// The `kind` field is one of `get_user_name`, `net_user_get_info`, `net_user_enum`,
+    /// `open_process_token`, `open_thread_token`, `get_token_information`, `load_net_api`, or
+    /// `invalid_priv`, and will not change between releases. The `path` field is always `None`.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let (kind, os_code) = match self {
+            Error::GetPriv {
+                operation: Operation::GetUserName,
+                error,
+            } => ("get_user_name", error.raw_os_error()),
+            Error::GetPriv {
+                operation: Operation::NetUserGetInfo,
+                error,
+            } => ("net_user_get_info", error.raw_os_error()),
+            Error::GetPriv {
+                operation: Operation::NetUserEnum,
+                error,
+            } => ("net_user_enum", error.raw_os_error()),
+            Error::GetPriv {
+                operation: Operation::OpenProcessToken,
+                error,
+            } => ("open_process_token", error.raw_os_error()),
+            Error::GetPriv {
+                operation: Operation::OpenThreadToken,
+                error,
+            } => ("open_thread_token", error.raw_os_error()),
+            Error::GetPriv {
+                operation: Operation::GetTokenInformation,
+                error,
+            } => ("get_token_information", error.raw_os_error()),
+            Error::GetPriv {
+                operation: Operation::LoadNetApi,
+                error,
+            } => ("load_net_api", error.raw_os_error()),
+            Error::InvalidPriv { .. } => ("invalid_priv", None),
+        };
+
+        let mut state = serializer.serialize_struct("Error", 4)?;
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("message", &format_args!("{self}"))?;
+        state.serialize_field("os_code", &os_code)?;
+        state.serialize_field("path", &None::<&str>)?;
+        state.end()
+    }
+}
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+    /// Code of the form `omst::N`, where `N` is the value of [`Error::code`].
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(format!("omst::{}", Error::code(self))))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match self {
+            Error::GetPriv {
+                operation: Operation::NetUserGetInfo | Operation::NetUserEnum,
+                ..
+            } => {
+                "make sure the Workstation service is running, and that the domain controller is \
+                 reachable for domain accounts"
+            }
+            Error::GetPriv {
+                operation: Operation::LoadNetApi,
+                ..
+            } => "netapi32.dll is missing, so only the current user can be classified",
+            Error::GetPriv {
+                operation:
+                    Operation::OpenProcessToken
+                    | Operation::OpenThreadToken
+                    | Operation::GetTokenInformation,
+                ..
+            } => "make sure the process isn't running with a restricted token",
+            Error::GetPriv { .. } | Error::InvalidPriv { .. } => return None,
+        };
+        Some(Box::new(help))
+    }
+}
+impl From<Error> for io::Error {
+    #[inline]
+    fn from(err: Error) -> io::Error {
+        match err {
+            Error::GetPriv { error, .. } => io::Error::from(error),
+            Error::InvalidPriv { .. } => io::Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::GetPriv { operation, error } => {
+                write!(f, "could not {operation} due to error: {error}")
+            }
+            Error::InvalidPriv { data } => {
+                write!(f, "user privileges had invalid value ({data:#x})")
+            }
+        }
+    }
+}
+
+impl Error {
+    /// Stable numeric code for this error.
+    ///
+    /// These values will not change between releases, and are distinct from the codes used by
+    /// other backends:
+    ///
+    /// | Code | Meaning                                  |
+    /// |------|------------------------------------------|
+    /// | 200  | `GetUserNameW` failed                    |
+    /// | 201  | `NetUserGetInfo` failed                  |
+    /// | 202  | `NetUserEnum` failed                     |
+    /// | 203  | `OpenProcessToken` failed                |
+    /// | 204  | `GetTokenInformation` failed             |
+    /// | 205  | `netapi32.dll` could not be loaded       |
+    /// | 206  | `OpenThreadToken` failed                 |
+    /// | 210  | user privileges had an unknown value     |
+    pub fn code(&self) -> u16 {
+        match self {
+            Error::GetPriv {
+                operation: Operation::GetUserName,
+                ..
+            } => 200,
+            Error::GetPriv {
+                operation: Operation::NetUserGetInfo,
+                ..
+            } => 201,
+            Error::GetPriv {
+                operation: Operation::NetUserEnum,
+                ..
+            } => 202,
+            Error::GetPriv {
+                operation: Operation::OpenProcessToken,
+                ..
+            } => 203,
+            Error::GetPriv {
+                operation: Operation::GetTokenInformation,
+                ..
+            } => 204,
+            Error::GetPriv {
+                operation: Operation::LoadNetApi,
+                ..
+            } => 205,
+            Error::GetPriv {
+                operation: Operation::OpenThreadToken,
+                ..
+            } => 206,
+            Error::InvalidPriv { .. } => 210,
+        }
+    }
+}
+
+/// Exported function, as returned by `GetProcAddress`.
+type Proc = unsafe extern "system" fn() -> isize;
+
+/// Signature of `NetUserGetInfo`.
+type NetUserGetInfo = unsafe extern "system" fn(PCWSTR, PCWSTR, u32, *mut *mut u8) -> u32;
+
+/// Signature of `NetUserEnum`.
+type NetUserEnum = unsafe extern "system" fn(
+    PCWSTR,
+    u32,
+    u32,
+    *mut *mut u8,
+    u32,
+    *mut u32,
+    *mut u32,
+    *mut u32,
+) -> u32;
+
+/// Signature of `NetApiBufferFree`.
+type NetApiBufferFree = unsafe extern "system" fn(*const c_void) -> u32;
+
+/// Functions from `netapi32.dll`.
+///
+/// These are loaded at runtime instead of being linked, since the library is missing on minimal
+/// installations like Nano Server, and we'd otherwise fail to start at all.
+struct NetApi {
+    /// `NetUserGetInfo`.
+    user_get_info: NetUserGetInfo,
+
+    /// `NetUserEnum`.
+    user_enum: NetUserEnum,
+
+    /// `NetApiBufferFree`.
+    api_buffer_free: NetApiBufferFree,
+}
+impl NetApi {
+    /// Loads the functions, returning the OS error code if that fails.
+    ///
+    /// # Safety
+    ///
+    /// The exported functions must have the signatures documented by Microsoft.
+    unsafe fn load() -> Result<NetApi, i32> {
+        let name = to_wide("netapi32.dll");
+        let module =
+            unsafe { LoadLibraryExW(name.as_ptr(), ptr::null_mut(), LOAD_LIBRARY_SEARCH_SYSTEM32) };
+        if module.is_null() {
+            return Err(io::Error::last_os_error().raw_os_error().unwrap_or(0));
+        }
+        let get = |name: &[u8]| match unsafe { GetProcAddress(module, name.as_ptr()) } {
+            Some(proc) => Ok(proc),
+            None => Err(io::Error::last_os_error().raw_os_error().unwrap_or(0)),
+        };
+
+        // the module is never freed, so, these stay valid for the rest of the program
+        unsafe {
+            Ok(NetApi {
+                user_get_info: mem::transmute::<Proc, NetUserGetInfo>(get(b"NetUserGetInfo\0")?),
+                user_enum: mem::transmute::<Proc, NetUserEnum>(get(b"NetUserEnum\0")?),
+                api_buffer_free: mem::transmute::<Proc, NetApiBufferFree>(get(
+                    b"NetApiBufferFree\0",
+                )?),
+            })
+        }
+    }
+}
+
+/// Loads `netapi32.dll` if it hasn't been loaded already.
+///
+/// With the `force-token` feature, this always fails as if the library were missing, so that the
+/// process token fallback used on minimal installations can be tested anywhere.
+fn net_api() -> Result<&'static NetApi, Error> {
+    if cfg!(feature = "force-token") {
+        return Err(Error::GetPriv {
+            operation: Operation::LoadNetApi,
+            error: IoError::new(
+                ErrorKind::Unsupported,
+                "netapi32.dll is disabled by the force-token feature",
+            ),
+        });
+    }
+    static NET_API: OnceLock<Result<NetApi, i32>> = OnceLock::new();
+    match NET_API.get_or_init(|| unsafe { NetApi::load() }) {
+        Ok(api) => Ok(api),
+        Err(code) => Err(Error::GetPriv {
+            operation: Operation::LoadNetApi,
+            error: IoError::from_raw_os_error(*code),
+        }),
+    }
+}
+
+#[repr(transparent)]
+struct UserInfoPtr(*mut USER_INFO_1);
+impl Drop for UserInfoPtr {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            // shouldn't be needed, but we're gonna do it anyway
+            let ptr = self.0 as *mut c_void;
+            self.0 = ptr::null_mut();
+
+            // buffers are only ever returned by functions we loaded
+            let Ok(api) = net_api() else {
+                abort();
+            };
+            let err = unsafe { (api.api_buffer_free)(ptr) };
+            if err != 0 {
+                abort();
+            }
+        }
+    }
+}
+
+/// Name of the current user, as a nul-terminated wide string.
+fn user_name() -> Result<Vec<u16>, Error> {
+    let mut uname = vec![0; UNLEN as usize + 1];
+    loop {
+        let mut ulen = uname.len() as u32;
+        let ok = unsafe { GetUserNameW(uname.as_mut_ptr(), &mut ulen) };
+        if ok != 0 {
+            uname.truncate(ulen as usize);
+            return Ok(uname);
+        }
+
+        // some names, like those of Microsoft accounts, are longer than `UNLEN`, in which case the
+        // required length is returned instead
+        let error = IoError::last_os_error();
+        if error.raw_os_error() != Some(ERROR_INSUFFICIENT_BUFFER as i32)
+            || ulen as usize <= uname.len()
+        {
+            return Err(Error::GetPriv {
+                operation: Operation::GetUserName,
+                error,
+            });
+        }
+        uname.resize(ulen as usize, 0);
+    }
+}
+
+/// Value of the `usri1_priv` field for the given nul-terminated user name.
+fn user_priv(uname: &[u16]) -> Result<u32, Error> {
+    let api = net_api()?;
+    let mut uinfo = UserInfoPtr(ptr::null_mut());
+    let uinfo_ptr = ptr::NonNull::from(&mut uinfo);
+    let err = unsafe {
+        (api.user_get_info)(
+            ptr::null(),
+            uname.as_ptr(),
+            1,
+            uinfo_ptr.cast::<*mut u8>().as_ptr(),
+        )
+    };
+    if err != 0 {
+        return Err(Error::GetPriv {
+            operation: Operation::NetUserGetInfo,
+            error: IoError::from_raw_os_error(err as i32),
+        });
+    }
+    Ok(unsafe { *uinfo.0 }.usri1_priv)
+}
+
+/// Converts the value of the `usri1_priv` field into a [`Priv`].
+fn to_priv(privs: u32) -> Result<Priv, Error> {
+    Ok(match privs {
+        USER_PRIV_ADMIN => Priv::Admin,
+        USER_PRIV_GUEST => Priv::Guest,
+        USER_PRIV_USER => Priv::User,
+        _ => return Err(Error::InvalidPriv { data: privs }),
+    })
+}
+
+/// Built-in service accounts, by their well-known SIDs, and their privileges.
+const SERVICES: [(WELL_KNOWN_SID_TYPE, Priv); 3] = [
+    (WinLocalSystemSid, Priv::Admin),
+    (WinLocalServiceSid, Priv::System),
+    (WinNetworkServiceSid, Priv::System),
+];
+
+/// Privileges of the built-in service account with the given SID, if it is one.
+fn service_priv(sid: PSID) -> Option<Priv> {
+    SERVICES
+        .iter()
+        .find(|&&(kind, _)| unsafe { IsWellKnownSid(sid, kind) } != 0)
+        .map(|&(_, r#priv)| r#priv)
+}
+
+/// Security identifier for the given nul-terminated user name, as a string.
+fn user_sid(uname: &[u16]) -> Option<String> {
+    let mut sid = lookup_sid(uname)?;
+    sid_string(sid.as_mut_ptr().cast())
+}
+
+/// Security identifier for the given nul-terminated user name.
+fn lookup_sid(uname: &[u16]) -> Option<[u8; SECURITY_MAX_SID_SIZE as usize]> {
+    let mut sid = [0u8; SECURITY_MAX_SID_SIZE as usize];
+    let mut sid_len = sid.len() as u32;
+    // domain names are at most `DNLEN` characters, but DNS names can be longer
+    let mut domain = [0u16; 256];
+    let mut domain_len = domain.len() as u32;
+    let mut sid_use: SID_NAME_USE = 0;
+    let ok = unsafe {
+        LookupAccountNameW(
+            ptr::null(),
+            uname.as_ptr(),
+            sid.as_mut_ptr().cast(),
+            &mut sid_len,
+            domain.as_mut_ptr(),
+            &mut domain_len,
+            &mut sid_use,
+        )
+    };
+    (ok != 0).then_some(sid)
+}
+
+/// Converts a security identifier into a string, like `S-1-5-18`.
+fn sid_string(sid: PSID) -> Option<String> {
+    let mut str_sid: PWSTR = ptr::null_mut();
+    let ok = unsafe { ConvertSidToStringSidW(sid, &mut str_sid) };
+    if ok == 0 {
+        return None;
+    }
+    let out = unsafe { from_wide_ptr(str_sid) };
+    unsafe { LocalFree(str_sid.cast()) };
+    Some(out)
+}
+
+/// Converts a nul-terminated wide string into a string.
+///
+/// # Safety
+///
+/// The pointer must point to a valid, nul-terminated wide string.
+unsafe fn from_wide_ptr(ptr: *const u16) -> String {
+    let len = (0..).take_while(|&i| unsafe { *ptr.add(i) } != 0).count();
+    String::from_utf16_lossy(unsafe { slice::from_raw_parts(ptr, len) })
+}
+
+/// Account on the local machine.
+#[derive(Clone, Debug)]
+pub struct User {
+    /// Name of the user.
+    pub name: String,
+
+    /// Value of the `usri1_priv` field.
+    pub privs: u32,
+}
+
+/// Lists all normal accounts on the local machine, using `NetUserEnum`.
+pub fn users() -> Result<Vec<User>, Error> {
+    let api = net_api()?;
+    let mut users = Vec::new();
+    let mut resume: u32 = 0;
+    loop {
+        let mut uinfo = UserInfoPtr(ptr::null_mut());
+        let uinfo_ptr = ptr::NonNull::from(&mut uinfo);
+        let mut read: u32 = 0;
+        let mut total: u32 = 0;
+        let err = unsafe {
+            (api.user_enum)(
+                ptr::null(),
+                1,
+                FILTER_NORMAL_ACCOUNT,
+                uinfo_ptr.cast::<*mut u8>().as_ptr(),
+                MAX_PREFERRED_LENGTH,
+                &mut read,
+                &mut total,
+                &mut resume,
+            )
+        };
+        if err != 0 && err != ERROR_MORE_DATA {
+            return Err(Error::GetPriv {
+                operation: Operation::NetUserEnum,
+                error: IoError::from_raw_os_error(err as i32),
+            });
+        }
+        if !uinfo.0.is_null() {
+            let infos = unsafe { slice::from_raw_parts(uinfo.0, read as usize) };
+            users.extend(infos.iter().map(|info| User {
+                name: unsafe { from_wide_ptr(info.usri1_name) },
+                privs: info.usri1_priv,
+            }));
+        }
+        if err != ERROR_MORE_DATA {
+            return Ok(users);
+        }
+    }
+}
+
+/// Lists and classifies all normal accounts on the local machine.
+pub fn accounts() -> Result<Vec<Account>, Error> {
+    Ok(users()?
+        .into_iter()
+        .map(|User { name, privs }| {
+            let permissions = to_priv(privs)
+                .map(Permissions::from)
+                .map_err(io::Error::from);
+            Account::new(name, None, permissions)
+        })
+        .collect())
+}
+
+/// Detailed report of how [`Priv`] was determined.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Report {
+    /// Name of the user.
+    pub name: String,
+
+    /// Security identifier of the user, if it could be determined.
+    pub sid: Option<String>,
+
+    /// Value of the `usri1_priv` field, or `None` for built-in service accounts, which don't have
+    /// one, and if [`token_groups`](Report::token_groups) is set.
+    pub privs: Option<u32>,
+
+    /// Whether the result was determined from the groups of the token, with [`token_priv`], because
+    /// `netapi32.dll` is missing or `NetUserGetInfo` doesn't know the user, like with Azure AD and
+    /// Microsoft accounts.
+    ///
+    /// Like with [`omst`], this is only done for the current user.
+    pub token_groups: bool,
+
+    /// Resulting privileges.
+    pub result: Priv,
+
+    /// Token whose user was classified, or `None` for users looked up by name.
+    ///
+    /// If this is [`TokenSource::Thread`], the calling thread was impersonating another user, and
+    /// the report describes that user instead of the owner of the process.
+    pub token: Option<TokenSource>,
+
+    /// Kind of session, from [`session`], if this is the current user's report and it was
+    /// detected.
+    pub session: Option<Session>,
+
+    /// Kind of token, with respect to UAC, if this is the current user's report and it could be
+    /// determined.
+    pub elevation_type: Option<ElevationType>,
+}
+impl Report {
+    /// Permissions corresponding to the result.
+    ///
+    /// Like [`permissions`], this is [`Permissions::Operator`] for administrators whose token is
+    /// limited by UAC.
+    #[inline]
+    pub fn permissions(&self) -> Permissions {
+        match (self.result, self.elevation_type) {
+            (Priv::Admin, Some(ElevationType::Limited)) => Permissions::Operator,
+            (result, _) => result.into(),
+        }
+    }
+
+    /// Human-readable, single-line trace of how the result was determined.
+    pub fn explain(&self) -> impl fmt::Display + '_ {
+        Explain(self)
+    }
+}
+
+/// Name of the constant for a [`Priv`].
+fn priv_name(r#priv: Priv) -> &'static str {
+    match r#priv {
+        Priv::Guest => "USER_PRIV_GUEST",
+        Priv::User => "USER_PRIV_USER",
+        Priv::System => "service account",
+        Priv::Admin => "USER_PRIV_ADMIN",
+    }
+}
+
+/// Description of the token group which decided a [`Priv`], for [`token_priv`].
+fn token_group_name(r#priv: Priv) -> &'static str {
+    match r#priv {
+        Priv::Guest => "Guests",
+        Priv::User => "no Administrators or Guests",
+        Priv::System => "service account",
+        Priv::Admin => "Administrators",
+    }
+}
+
+/// Return value of [`Report::explain`].
+struct Explain<'a>(&'a Report);
+impl fmt::Display for Explain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Report {
+            name,
+            sid,
+            privs,
+            result,
+            token,
+            token_groups,
+            elevation_type,
+            ..
+        } = self.0;
+        let level = self.0.permissions();
+        let user = match token {
+            Some(TokenSource::Thread) => "impersonated user",
+            Some(TokenSource::Process) | None => "user",
+        };
+        match privs {
+            Some(privs) => write!(
+                f,
+                "usri1_priv of {user} {name} is {}({privs:#x}) from NetUserGetInfo",
+                priv_name(*result),
+            )?,
+            None if *token_groups => write!(
+                f,
+                "{user} {name} has {} in its token's groups, since NetUserGetInfo is unavailable",
+                token_group_name(*result),
+            )?,
+            None => write!(
+                f,
+                "{user} {name} is the built-in service account {}",
+                sid.as_deref().unwrap_or("(unknown SID)"),
+            )?,
+        }
+        if let (Priv::Admin, Some(ElevationType::Limited)) = (result, elevation_type) {
+            write!(f, ", with a limited UAC token")?;
+        }
+        write!(f, " → {level} `{}`", level.be())
+    }
+}
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "user: {}", self.name)?;
+        match &self.sid {
+            Some(sid) => writeln!(f, "sid: {sid}")?,
+            None => writeln!(f, "sid: (unknown)")?,
+        }
+        match self.privs {
+            Some(privs) => writeln!(f, "priv: {} ({privs:#x})", priv_name(self.result))?,
+            None if self.token_groups => writeln!(
+                f,
+                "priv: (from token groups: {})",
+                token_group_name(self.result)
+            )?,
+            None => writeln!(f, "priv: (built-in service account)")?,
+        }
+        if let Some(token) = self.token {
+            writeln!(f, "token: {token}")?;
+        }
+        if let Some(session) = self.session {
+            writeln!(f, "session: {session}")?;
+        }
+        if let Some(elevation_type) = self.elevation_type {
+            writeln!(f, "elevation type: {elevation_type}")?;
+        }
+        let level = self.permissions();
+        write!(f, "level: {level} ({})", level.be())
+    }
+}
+
+/// Determine a detailed [`Report`] for the current user.
+///
+/// This performs the same logic as [`omst`], but additionally looks up the user's SID and keeps
+/// track of the data that was consulted.
+pub fn report() -> Result<Report, Error> {
+    let token = Token::current()?;
+    let uname = user_name()?;
+    let (privs, result, token_groups) = match token.service_priv() {
+        Ok(Some(r#priv)) => (None, r#priv, false),
+        _ => match current_priv(&uname)? {
+            (Some(privs), r#priv) => (Some(privs), r#priv, false),
+            (None, r#priv) => (None, r#priv, true),
+        },
+    };
+    Ok(Report {
+        name: String::from_utf16_lossy(&uname[..uname.len() - 1]),
+        sid: user_sid(&uname),
+        privs,
+        result,
+        token_groups,
+        token: Some(token.source),
+        session: session(),
+        elevation_type: token.elevation_type().ok(),
+    })
+}
+
+/// Detects the kind of session the current process is running in, if possible.
+///
+/// This is purely a heuristic: [`Session::Ssh`] if `SSH_CONNECTION` or `SSH_CLIENT` is set, like
+/// with OpenSSH for Windows, or [`Session::Graphical`] if `SESSIONNAME` is set, which it is for
+/// both the console and Remote Desktop. Services and scheduled tasks have neither.
+pub fn session() -> Option<Session> {
+    let is_set = |var| std::env::var_os(var).is_some_and(|value| !value.is_empty());
+    if is_set("SSH_CONNECTION") || is_set("SSH_CLIENT") {
+        Some(Session::Ssh)
+    } else if is_set("SESSIONNAME") {
+        Some(Session::Graphical)
+    } else {
+        None
+    }
+}
+
+/// Determine a detailed [`Report`] for an arbitrary user name.
+pub fn report_of_user(name: &str) -> Result<Report, Error> {
+    report_wide(to_wide(name))
+}
+
+/// Determine [`Priv`] for an arbitrary user name.
+///
+/// This uses the same logic as [`omst`], but for a user other than the current one. Built-in
+/// service accounts, like `SYSTEM`, are recognized by the SIDs of their names.
+pub fn of_user(name: &str) -> Result<Priv, Error> {
+    let uname = to_wide(name);
+    match user_priv(&uname) {
+        Ok(privs) => to_priv(privs),
+        Err(err) => name_service_priv(&uname).ok_or(err),
+    }
+}
+
+/// Determines the [`Permissions`] for an arbitrary user name.
+///
+/// Only the current process has a token whose elevation type can be checked, so this is never
+/// [`Permissions::Operator`]; administrators are always [`Permissions::Absolute`].
+#[inline]
+pub fn permissions_of_user(name: &str) -> Result<Permissions, Error> {
+    of_user(name).map(Permissions::from)
+}
+
+/// Privileges of the built-in service account with the given nul-terminated name, if it is one.
+fn name_service_priv(uname: &[u16]) -> Option<Priv> {
+    let mut sid = lookup_sid(uname)?;
+    service_priv(sid.as_mut_ptr().cast())
+}
+
+/// Converts a string to a nul-terminated wide string.
+fn to_wide(name: &str) -> Vec<u16> {
+    name.encode_utf16().chain(Some(0)).collect()
+}
+
+/// Determine a detailed [`Report`] for the given nul-terminated user name.
+fn report_wide(uname: Vec<u16>) -> Result<Report, Error> {
+    let (privs, result) = match user_priv(&uname) {
+        Ok(privs) => (Some(privs), to_priv(privs)?),
+        Err(err) => (None, name_service_priv(&uname).ok_or(err)?),
+    };
+    Ok(Report {
+        name: String::from_utf16_lossy(&uname[..uname.len() - 1]),
+        sid: user_sid(&uname),
+        privs,
+        result,
+        token_groups: false,
+        token: None,
+        session: None,
+        elevation_type: None,
+    })
+}
+
+/// Determine [`Priv`] based upon the Windows API `NetUserGetInfo` function.
+///
+/// The Windows API has several different ways of getting user permissions, but the way this
+/// library does so is by obtaining a `USER_INFO_1` struct and checking the `usri1_priv` field;
+/// the value of this field is either `USER_PRIV_GUEST`, `USER_PRIV_USER`, or `USER_PRIV_ADMIN`
+/// depending on the permission level of the user, and these are mapped to [`Priv::Guest`],
+/// [`Priv::User`], and [`Priv::Admin`] respectively.
+///
+/// To actually call the `NetUserGetInfo` function, we first call `GetUserNameW` to get the current
+/// user name, then pass this to `NetUserGetInfo` to obtain a `USER_INFO_1` struct with the data we
+/// need.
+///
+/// The implementation was derived from
+/// [this answer on Stack Overflow](https://stackoverflow.com/a/45125995).
+///
+/// If the calling thread is impersonating another user, that user is classified instead, since
+/// `GetUserNameW` returns their name; [`report`] says whether this happened.
+///
+/// Built-in service accounts don't have a `USER_INFO`, so, they're detected by the SID of the token
+/// first: LocalSystem (`S-1-5-18`) is [`Priv::Admin`], since it has every privilege an
+/// administrator has, and LocalService (`S-1-5-19`) and NetworkService (`S-1-5-20`) are
+/// [`Priv::System`].
+///
+/// On minimal installations without `netapi32.dll`, like Nano Server, the groups of the token are
+/// checked instead: members of the built-in Administrators group are [`Priv::Admin`],
+/// members of the built-in Guests group are [`Priv::Guest`], and everyone else is [`Priv::User`].
+/// The same is done for accounts which aren't local accounts, like Azure AD and Microsoft accounts,
+/// which `NetUserGetInfo` reports as nonexistent.
+pub fn omst() -> Result<Priv, Error> {
+    // if the token can't be read, `NetUserGetInfo` might still work
+    if let Ok(Some(r#priv)) = Token::current().and_then(|token| token.service_priv()) {
+        return Ok(r#priv);
+    }
+    current_priv(&user_name()?).map(|(_, r#priv)| r#priv)
+}
+
+/// Determine [`Priv`] for the current user with the given nul-terminated name, falling back to
+/// [`token_priv`] like [`omst`] does.
+///
+/// Returns the value of the `usri1_priv` field alongside the result, or `None` if the fallback
+/// was used.
+fn current_priv(uname: &[u16]) -> Result<(Option<u32>, Priv), Error> {
+    if net_api().is_err() {
+        return Ok((None, token_priv()?));
+    }
+    match user_priv(uname) {
+        Ok(privs) => Ok((Some(privs), to_priv(privs)?)),
+        Err(Error::GetPriv { error, .. })
+            if error.raw_os_error() == Some(NERR_UserNotFound as i32) =>
+        {
+            Ok((None, token_priv()?))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Determine [`Permissions`] for the current user, including whether they can elevate.
+///
+/// This is [`omst`], except that administrators whose token is limited by UAC are
+/// [`Permissions::Operator`], since they can elevate, but aren't elevated. If the kind of token
+/// can't be determined, they're [`Permissions::Absolute`], like with [`omst`].
+pub fn permissions() -> Result<Permissions, Error> {
+    let r#priv = omst()?;
+    let elevation_type = match r#priv {
+        Priv::Admin => Token::current()
+            .and_then(|token| token.elevation_type())
+            .ok(),
+        _ => None,
+    };
+    Ok(match (r#priv, elevation_type) {
+        (Priv::Admin, Some(ElevationType::Limited)) => Permissions::Operator,
+        (r#priv, _) => r#priv.into(),
+    })
+}
+
+/// Determine [`Priv`] from the groups of the current token.
+///
+/// This is the impersonation token of the calling thread, if it has one, and the process token
+/// otherwise.
+///
+/// Groups which are only used to deny access, like the Administrators group of a limited UAC
+/// token, are included, since they still describe the account.
+///
+/// Built-in service accounts are detected the same way as with [`omst`]. This is what [`omst`]
+/// falls back to when `netapi32.dll` is missing.
+pub fn token_priv() -> Result<Priv, Error> {
+    let token = Token::current()?;
+    if let Some(r#priv) = token.service_priv()? {
+        return Ok(r#priv);
+    }
+    let buf = token.info(TokenGroups)?;
+    let groups = unsafe { &*buf.as_ptr().cast::<TOKEN_GROUPS>() };
+    let groups =
+        unsafe { slice::from_raw_parts(groups.Groups.as_ptr(), groups.GroupCount as usize) };
+    let is_member = |kind: WELL_KNOWN_SID_TYPE| {
+        let mut sid = [0u8; SECURITY_MAX_SID_SIZE as usize];
+        let mut sid_len = sid.len() as u32;
+        let ok = unsafe {
+            CreateWellKnownSid(kind, ptr::null_mut(), sid.as_mut_ptr().cast(), &mut sid_len)
+        };
+        ok != 0
+            && groups
+                .iter()
+                .any(|group| unsafe { EqualSid(group.Sid, sid.as_mut_ptr().cast()) } != 0)
+    };
+    Ok(if is_member(WinBuiltinAdministratorsSid) {
+        Priv::Admin
+    } else if is_member(WinBuiltinGuestsSid) {
+        Priv::Guest
+    } else {
+        Priv::User
+    })
+}
+
+/// Which token describes the current user.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum TokenSource {
+    /// The process token, since the calling thread isn't impersonating anyone.
+    Process,
+
+    /// The impersonation token of the calling thread.
+    Thread,
+}
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            TokenSource::Process => "process",
+            TokenSource::Thread => "thread (impersonating)",
+        })
+    }
+}
+
+/// Token for the current user, closed when dropped.
+struct Token {
+    /// Handle to the token.
+    handle: HANDLE,
+
+    /// Where the token came from.
+    source: TokenSource,
+}
+impl Token {
+    /// Opens the impersonation token of the calling thread, falling back to the process token if
+    /// the thread isn't impersonating anyone.
+    fn current() -> Result<Token, Error> {
+        let mut handle: HANDLE = ptr::null_mut();
+
+        // the access check is done against the process, so that identification-level
+        // impersonation tokens, which can't open anything themselves, can still be queried
+        let ok = unsafe { OpenThreadToken(GetCurrentThread(), TOKEN_QUERY, 1, &mut handle) };
+        if ok != 0 {
+            return Ok(Token {
+                handle,
+                source: TokenSource::Thread,
+            });
+        }
+        let error = IoError::last_os_error();
+        if error.raw_os_error() != Some(ERROR_NO_TOKEN as i32) {
+            return Err(Error::GetPriv {
+                operation: Operation::OpenThreadToken,
+                error,
+            });
+        }
+
+        let ok = unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut handle) };
+        if ok == 0 {
+            return Err(Error::GetPriv {
+                operation: Operation::OpenProcessToken,
+                error: IoError::last_os_error(),
+            });
+        }
+        Ok(Token {
+            handle,
+            source: TokenSource::Process,
+        })
+    }
+
+    /// Privileges of the token's user, if it's a built-in service account.
+    fn service_priv(&self) -> Result<Option<Priv>, Error> {
+        let buf = self.info(TokenUser)?;
+        let sid = unsafe { (*buf.as_ptr().cast::<TOKEN_USER>()).User.Sid };
+        Ok(service_priv(sid))
+    }
+
+    /// Kind of token, with respect to UAC.
+    #[allow(non_upper_case_globals)]
+    fn elevation_type(&self) -> Result<ElevationType, Error> {
+        let buf = self.info(TokenElevationType)?;
+        Ok(
+            match unsafe { *buf.as_ptr().cast::<TOKEN_ELEVATION_TYPE>() } {
+                TokenElevationTypeFull => ElevationType::Full,
+                TokenElevationTypeLimited => ElevationType::Limited,
+                _ => ElevationType::Default,
+            },
+        )
+    }
+
+    /// Gets information about the token.
+    ///
+    /// The buffer is made of `u64` so that it's aligned enough for any of the returned structs.
+    fn info(&self, class: TOKEN_INFORMATION_CLASS) -> Result<Vec<u64>, Error> {
+        // the first call is expected to fail, and only tells us the length
+        let mut len: u32 = 0;
+        unsafe { GetTokenInformation(self.handle, class, ptr::null_mut(), 0, &mut len) };
+        let mut buf = vec![0u64; (len as usize).div_ceil(8)];
+        let ok = unsafe {
+            GetTokenInformation(
+                self.handle,
+                class,
+                buf.as_mut_ptr().cast(),
+                (buf.len() * 8) as u32,
+                &mut len,
+            )
+        };
+        if ok == 0 {
+            return Err(Error::GetPriv {
+                operation: Operation::GetTokenInformation,
+                error: IoError::last_os_error(),
+            });
+        }
+        Ok(buf)
+    }
+}
+impl Drop for Token {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.handle) };
+    }
+}
+
+/// Kind of token the current process has, with respect to UAC.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum ElevationType {
+    /// UAC is disabled, or the user isn't an administrator, so, there's no linked token.
+    Default,
+
+    /// The token is elevated, and is linked to a limited token.
+    Full,
+
+    /// The token is limited, and is linked to an elevated token.
+    Limited,
+}
+impl fmt::Display for ElevationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            ElevationType::Default => "default",
+            ElevationType::Full => "full",
+            ElevationType::Limited => "limited",
+        })
+    }
+}
+
+/// Elevation state of the current token, as returned by [`elevation`].
+///
+/// Unlike [`Report`], this describes the token as well as the account, which is what's needed to
+/// debug UAC. Like [`token_priv`], this is the impersonation token of the calling thread, if it has
+/// one, and the process token otherwise.
+#[derive(Clone, Debug)]
+pub struct Elevation {
+    /// Privileges of the account, from `NetUserGetInfo`.
+    pub account: Priv,
+
+    /// Whether the token is elevated.
+    pub elevated: bool,
+
+    /// Kind of token, which determines whether there's a linked token.
+    pub elevation_type: ElevationType,
+
+    /// Relative identifier of the token's mandatory integrity level, like
+    /// `SECURITY_MANDATORY_MEDIUM_RID`.
+    pub integrity: Option<u32>,
+
+    /// Which token was described.
+    pub token: TokenSource,
+}
+impl Elevation {
+    /// Whether the token is linked to another one, i.e. whether the user can elevate or has.
+    #[inline]
+    pub fn has_linked_token(&self) -> bool {
+        self.elevation_type != ElevationType::Default
+    }
+}
+
+/// Name of an integrity level.
+fn integrity_name(rid: u32) -> &'static str {
+    match rid as i32 {
+        SECURITY_MANDATORY_UNTRUSTED_RID => "untrusted",
+        SECURITY_MANDATORY_LOW_RID => "low",
+        SECURITY_MANDATORY_MEDIUM_RID => "medium",
+        rid if rid == SECURITY_MANDATORY_MEDIUM_PLUS_RID as i32 => "medium plus",
+        SECURITY_MANDATORY_HIGH_RID => "high",
+        SECURITY_MANDATORY_SYSTEM_RID => "system",
+        0x5000 => "protected process",
+        _ => "unknown",
+    }
+}
+
+impl fmt::Display for Elevation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "account: {}", priv_name(self.account))?;
+        writeln!(f, "token: {}", self.token)?;
+        writeln!(f, "elevated: {}", if self.elevated { "yes" } else { "no" })?;
+        writeln!(f, "elevation type: {}", self.elevation_type)?;
+        writeln!(
+            f,
+            "linked token: {}",
+            if self.has_linked_token() { "yes" } else { "no" }
+        )?;
+        match self.integrity {
+            Some(rid) => write!(f, "integrity: {} ({rid:#x})", integrity_name(rid)),
+            None => write!(f, "integrity: (unknown)"),
+        }
+    }
+}
+
+/// Determine the [`Elevation`] state of the current token.
+pub fn elevation() -> Result<Elevation, Error> {
+    let account = omst()?;
+    let token = Token::current()?;
+
+    let buf = token.info(TokenElevation)?;
+    let elevated = unsafe { *buf.as_ptr().cast::<TOKEN_ELEVATION>() }.TokenIsElevated != 0;
+
+    let elevation_type = token.elevation_type()?;
+
+    let buf = token.info(TokenIntegrityLevel)?;
+    let sid = unsafe { (*buf.as_ptr().cast::<TOKEN_MANDATORY_LABEL>()).Label.Sid };
+    let integrity = match unsafe { *GetSidSubAuthorityCount(sid) } {
+        0 => None,
+        count => Some(unsafe { *GetSidSubAuthority(sid, u32::from(count) - 1) }),
+    };
+
+    Ok(Elevation {
+        account,
+        elevated,
+        elevation_type,
+        integrity,
+        token: token.source,
+    })
+}
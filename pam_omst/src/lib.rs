@@ -0,0 +1,88 @@
+//! PAM module which exports whomst thou art to the session environment.
+//!
+//! When added to a session stack, like `session optional pam_omst.so`, this sets `OMST` to the
+//! symbol for the user's level and `OMST_LEVEL` to its name, so that shells and MOTD scripts can
+//! use them without running `omst` themselves. If the level can't be determined, these are set to
+//! `?` and `unknown` instead.
+#![cfg(not(windows))]
+#![warn(unsafe_op_in_unsafe_fn)]
+use omst::Permissions;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+
+/// Opaque PAM handle.
+#[repr(C)]
+pub struct PamHandle {
+    _private: [u8; 0],
+}
+
+/// Successful function return.
+const PAM_SUCCESS: c_int = 0;
+
+/// Ignore this module.
+const PAM_IGNORE: c_int = 25;
+
+// these are resolved from the libpam which loads the module, so, we don't link it ourselves
+extern "C" {
+    fn pam_get_user(pamh: *mut PamHandle, user: *mut *const c_char, prompt: *const c_char)
+        -> c_int;
+    fn pam_putenv(pamh: *mut PamHandle, name_value: *const c_char) -> c_int;
+}
+
+/// Sets an environment variable for the session, returning whether it was successful.
+///
+/// # Safety
+///
+/// `pamh` must be the handle passed to the module.
+unsafe fn putenv(pamh: *mut PamHandle, name: &str, value: &str) -> bool {
+    let Ok(var) = CString::new(format!("{name}={value}")) else {
+        return false;
+    };
+    unsafe { pam_putenv(pamh, var.as_ptr()) == PAM_SUCCESS }
+}
+
+/// Exports the user's level when the session is opened.
+///
+/// # Safety
+///
+/// Must only be called by PAM.
+#[no_mangle]
+pub unsafe extern "C" fn pam_sm_open_session(
+    pamh: *mut PamHandle,
+    _flags: c_int,
+    _argc: c_int,
+    _argv: *const *const c_char,
+) -> c_int {
+    let mut user = ptr::null();
+    if unsafe { pam_get_user(pamh, &mut user, ptr::null()) } != PAM_SUCCESS || user.is_null() {
+        return PAM_IGNORE;
+    }
+    let Ok(user) = unsafe { CStr::from_ptr(user) }.to_str() else {
+        return PAM_IGNORE;
+    };
+
+    // the session is opened by a privileged process, so, we have to look up the user by name
+    let level = omst::of_user(user).ok();
+    let symbol = level.map_or('?', Permissions::be).to_string();
+    let name = level.map_or("unknown".to_owned(), |level| level.to_string());
+    if unsafe { putenv(pamh, "OMST", &symbol) && putenv(pamh, "OMST_LEVEL", &name) } {
+        PAM_SUCCESS
+    } else {
+        PAM_IGNORE
+    }
+}
+
+/// Does nothing when the session is closed.
+///
+/// # Safety
+///
+/// Must only be called by PAM.
+#[no_mangle]
+pub unsafe extern "C" fn pam_sm_close_session(
+    _pamh: *mut PamHandle,
+    _flags: c_int,
+    _argc: c_int,
+    _argv: *const *const c_char,
+) -> c_int {
+    PAM_SUCCESS
+}
@@ -2,150 +2,313 @@
 //!
 //! This crate provides functions which ultimately are used to provide the functionality for the
 //! `omst` binary.
+//!
+//! It's a facade over the workspace's crates: the shared types live in `omst-core`, and each
+//! backend in its own crate, re-exported here under its module name.
 #![warn(unsafe_op_in_unsafe_fn)]
-use core::fmt;
 use std::io;
+use std::panic;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+pub use omst_core::{
+    Account, BackendInfo, Cost, DisplayResult, IoError, ParsePermissionsError, Permissions,
+    ResultExt, Session,
+};
 
 /// Implementation for Windows API.
 #[cfg(windows)]
-pub mod winapi;
+pub use omst_windows as winapi;
 
 /// Implementation for `shadow-utils`.
 #[cfg(not(windows))]
-pub mod shadow;
+pub use omst_unix as shadow;
+
+/// Loader for external classification backends.
+#[cfg(feature = "plugins")]
+pub mod plugin;
+
+/// Watching the current user's level for changes.
+mod watch;
+
+pub use crate::watch::{Cause, Event, Watcher};
+
+/// Implementation which always returns [`Permissions::User`], without touching the OS.
+#[cfg(feature = "stub")]
+mod stub;
 
 // Actual implementation.
-#[cfg(not(windows))]
+#[cfg(all(not(windows), not(feature = "stub")))]
 use crate::shadow as r#impl;
-#[cfg(windows)]
+#[cfg(feature = "stub")]
+use crate::stub as r#impl;
+#[cfg(all(windows, not(feature = "stub")))]
 use crate::winapi as r#impl;
 
+/// Information about the implementation used by [`omst`].
+#[inline]
+pub fn backend() -> BackendInfo {
+    r#impl::BACKEND
+}
+
+/// Name of the implementation used by [`omst`].
+///
+/// Shorthand for `backend().name`.
+#[inline]
+pub fn backend_name() -> &'static str {
+    backend().name
+}
+
+/// Detailed report of how a user's [`Permissions`] were determined.
+///
+/// The exact contents depend on the implementation, but will always display as a human-readable
+/// summary.
+pub use crate::r#impl::Report;
+
 /// Determines a user's [`Permissions`].
+///
+/// Users who can elevate, but aren't elevated, are [`Permissions::Operator`]; use
+/// [`Permissions::flatten`] to treat them as ordinary users.
 #[inline]
 pub fn omst() -> io::Result<Permissions> {
-    r#impl::omst()
-        .map(Permissions::from)
-        .map_err(io::Error::from)
-}
-
-/// Summary of a user's permissions.
-///
-/// This indicator is purely informational and should not be assumed to have any level of security.
-#[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord, Debug)]
-#[repr(u8)]
-pub enum Permissions {
-    /// Restricted permissions.
-    ///
-    /// Usually, these users will be ephemeral and have their files deleted after logging out.
-    ///
-    /// # System-specific behavior
-    ///
-    /// On POSIX-based systems, this includes at least the `nobody` user,
-    /// but may include other dedicated guest users.
-    ///
-    /// On Windows, this is specifically guest users.
-    Guest = b'%',
-
-    /// Ordinary user permissions.
-    ///
-    /// Users that represent a real person will have this permission level.
-    User = b'$',
-
-    /// System service permissions.
-    ///
-    /// These are users dedicated to running system services who may have elevated privileges, but
-    /// do not have absolute system access.
-    ///
-    /// # System-specific behavior
-    ///
-    /// This is mostly only available on unix-family systems, for users with a UID below `UID_MIN`.
-    /// In most cases, this means a UID below 1000, but some systems may start allocating ordinary
-    /// users at UID 500.
-    System = b'@',
-
-    /// Absolute permissions.
-    ///
-    /// These users have full access to the system, to the extent that the OS allows.
-    ///
-    /// # System-specific behavior
-    ///
-    /// On unix-family systems and Android, this is the root user.
-    ///
-    /// On Windows, this refers to users with administrator privileges.
-    Absolute = b'#',
-}
-impl Permissions {
-    /// The permissions as a single ASCII character.
-    ///
-    /// In most cases, you want to use [`be`](Self::be) instead.
-    #[inline]
-    pub fn byte(self) -> u8 {
-        self as u8
-    }
+    r#impl::permissions().map_err(io::Error::from)
+}
 
-    /// The permissions as a single character.
-    ///
-    /// Most often used as `omst().be()`.
-    #[inline]
-    pub fn be(self) -> char {
-        self.byte() as char
+/// Determines a detailed [`Report`] of a user's [`Permissions`].
+#[inline]
+pub fn report() -> io::Result<Report> {
+    r#impl::report().map_err(io::Error::from)
+}
+
+/// Runs a lookup on another thread, failing with [`io::ErrorKind::TimedOut`] if it doesn't finish
+/// within `timeout`.
+///
+/// Lookups can't be cancelled, so, the thread is left running in the background if it times out.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let level = omst::with_timeout(Duration::from_secs(5), omst::omst);
+/// ```
+pub fn with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    lookup: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+    let (tx, rx) = mpsc::sync_channel(1);
+    thread::Builder::new()
+        .name("omst lookup".to_owned())
+        .spawn(move || {
+            let _ = tx.send(lookup());
+        })?;
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("lookup did not finish within {timeout:?}"),
+        )),
+        Err(RecvTimeoutError::Disconnected) => Err(io::Error::other("lookup panicked")),
     }
 }
-impl fmt::Display for Permissions {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.pad(match self {
-            Permissions::Guest => "guest",
-            Permissions::User => "user",
-            Permissions::System => "system",
-            Permissions::Absolute => "aboslute",
-        })
+
+/// Detects the kind of [`Session`] the current process is running in, if possible.
+///
+/// This is a heuristic based on environment variables and, on unix-family systems, the process's
+/// ancestors and terminal; see the backends' `session` functions for details.
+#[inline]
+pub fn session() -> Option<Session> {
+    #[cfg(not(windows))]
+    return shadow::session();
+    #[cfg(windows)]
+    return winapi::session();
+}
+
+/// Time [`quick`] waits for the backend before giving up.
+pub const QUICK_BUDGET: Duration = Duration::from_millis(200);
+
+/// Determines the current user's [`Permissions`] on a best-effort basis, for callers like prompts
+/// which never want an error.
+///
+/// This runs [`omst`], which already falls back to other sources when it can, on another thread,
+/// waiting at most [`QUICK_BUDGET`] for it. If it fails, times out, or panics, the effective user
+/// ID is checked as a last resort on unix-family systems, since root is always
+/// [`Permissions::Absolute`]. Otherwise, the level is unknown, and `None` is returned; callers
+/// decide what to show instead, like `?`.
+///
+/// Lookups can't be cancelled, so, one which times out keeps running in the background. Later calls
+/// wait for that lookup instead of starting another, so that at most one thread is ever left
+/// behind, even if the backend hangs forever. Once it finishes, the next call starts a new lookup.
+///
+/// This never panics, and never blocks for much longer than the budget.
+///
+/// # Examples
+///
+/// ```
+/// let symbol = omst::quick().map_or('?', omst::Permissions::be);
+/// ```
+pub fn quick() -> Option<Permissions> {
+    let result = quick_lookup();
+    #[cfg(not(windows))]
+    if result.is_none() && shadow::euid() == 0 {
+        return Some(Permissions::Absolute);
     }
+    result
 }
 
-/// Displayed version of result for `omst-be`.
-pub struct DisplayResult(io::Result<Permissions>);
-impl fmt::Display for DisplayResult {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.0 {
-            Ok(ok) => fmt::Display::fmt(ok, f),
-            Err(err) => fmt::Display::fmt(err, f),
+/// Determines the current user's [`Permissions`] like [`quick`], returning `unknown` if the level
+/// is unknown.
+///
+/// This is for callers which always need a level, and have one which is safe to assume, like
+/// [`Permissions::User`] for a prompt.
+///
+/// # Examples
+///
+/// ```
+/// use omst::Permissions;
+///
+/// let symbol = omst::quick_or(Permissions::User).be();
+/// ```
+#[inline]
+pub fn quick_or(unknown: Permissions) -> Permissions {
+    quick().unwrap_or(unknown)
+}
+
+/// Lookup started by [`quick`], which later calls share until it finishes.
+#[derive(Default)]
+struct QuickLookup {
+    /// Result, once the lookup finished; failures and panics are `Some(None)`.
+    result: Mutex<Option<Option<Permissions>>>,
+
+    /// Notified once the result is set.
+    done: Condvar,
+}
+
+/// Lookup which [`quick`] is waiting for, if it hasn't finished yet.
+static IN_FLIGHT: Mutex<Option<Arc<QuickLookup>>> = Mutex::new(None);
+
+/// Waits at most [`QUICK_BUDGET`] for the lookup in flight, starting one if there isn't one.
+fn quick_lookup() -> Option<Permissions> {
+    let lookup = {
+        let mut in_flight = IN_FLIGHT.lock().unwrap_or_else(PoisonError::into_inner);
+        match &*in_flight {
+            Some(lookup) => Arc::clone(lookup),
+            None => {
+                let lookup = Arc::new(QuickLookup::default());
+                let theirs = Arc::clone(&lookup);
+                thread::Builder::new()
+                    .name("omst quick lookup".to_owned())
+                    .spawn(move || {
+                        let result = panic::catch_unwind(omst).ok().and_then(Result::ok);
+                        *theirs.result.lock().unwrap_or_else(PoisonError::into_inner) =
+                            Some(result);
+                        theirs.done.notify_all();
+
+                        // later calls should start over, instead of getting an old result
+                        let mut in_flight =
+                            IN_FLIGHT.lock().unwrap_or_else(PoisonError::into_inner);
+                        if in_flight
+                            .as_ref()
+                            .is_some_and(|lookup| Arc::ptr_eq(lookup, &theirs))
+                        {
+                            *in_flight = None;
+                        }
+                    })
+                    .ok()?;
+                *in_flight = Some(Arc::clone(&lookup));
+                lookup
+            }
         }
-    }
+    };
+    let result = lookup.result.lock().unwrap_or_else(PoisonError::into_inner);
+    let (result, _) = lookup
+        .done
+        .wait_timeout_while(result, QUICK_BUDGET, |result| result.is_none())
+        .unwrap_or_else(PoisonError::into_inner);
+    (*result).flatten()
 }
 
-/// Extension trait for return value of [`omst`].
-pub trait ResultExt: Sized {
-    /// The permissions as a single ASCII character.
-    ///
-    /// In most cases, you want to use [`be`](Self::be) instead.
-    fn byte(self) -> u8;
+/// Lists all local accounts and their [`Permissions`].
+#[inline]
+pub fn accounts() -> io::Result<Vec<Account>> {
+    r#impl::accounts().map_err(io::Error::from)
+}
 
-    /// The permissions as a single character.
-    ///
-    /// Most often used as `omst().be()`.
-    fn be(self) -> char;
+/// Determines the [`Permissions`] of an arbitrary user, by name.
+#[inline]
+pub fn of_user(name: &str) -> io::Result<Permissions> {
+    r#impl::permissions_of_user(name).map_err(io::Error::from)
+}
 
-    /// The permissions as a displayable value.
-    ///
-    /// Will fully explain errors.
-    fn display(self) -> DisplayResult;
+/// Determines a detailed [`Report`] of an arbitrary user's [`Permissions`], by name.
+#[inline]
+pub fn report_of_user(name: &str) -> io::Result<Report> {
+    r#impl::report_of_user(name).map_err(io::Error::from)
 }
-impl ResultExt for io::Result<Permissions> {
-    #[inline]
-    fn byte(self) -> u8 {
-        self.map_or(b'?', Permissions::byte)
-    }
-    #[inline]
-    fn be(self) -> char {
-        self.byte() as char
-    }
-    #[inline]
-    fn display(self) -> DisplayResult {
-        DisplayResult(self)
-    }
+
+/// Determines the [`Permissions`] of a set of credentials, like those of a client connecting to a
+/// Unix socket.
+///
+/// This always uses the [`shadow`] backend. See [`shadow::of_credentials`] for details; the group
+/// IDs, including `gid`, are checked with [`shadow::with_groups`].
+#[cfg(not(windows))]
+#[inline]
+pub fn of_credentials(uid: u32, gid: u32, groups: &[u32]) -> io::Result<Permissions> {
+    shadow::of_credentials(uid, gid, groups).map_err(io::Error::from)
+}
+
+/// Determines the [`Permissions`] of the peer of a connected Unix socket.
+///
+/// This always uses the [`shadow`] backend. See [`shadow::peer_credentials`] and
+/// [`shadow::peer_groups`] for how the peer's credentials are determined. The supplementary groups
+/// are only known on Linux, so, elsewhere, peers which are only in an admin group as a
+/// supplementary group aren't [`Permissions::Operator`].
+#[cfg(not(windows))]
+#[inline]
+pub fn of_peer<S: std::os::fd::AsFd>(socket: S) -> io::Result<Permissions> {
+    shadow::of_peer(socket).map_err(io::Error::from)
 }
 
 #[test]
 fn is_known() {
     assert!(omst().is_ok());
 }
+
+#[test]
+fn backend_matches_module() {
+    #[cfg(all(not(windows), not(feature = "stub")))]
+    assert_eq!(backend_name(), "shadow");
+    #[cfg(all(windows, not(feature = "stub")))]
+    assert_eq!(backend_name(), "winapi");
+    #[cfg(feature = "stub")]
+    assert_eq!(backend_name(), "stub");
+    assert_eq!(backend().cost.is_pure(), cfg!(feature = "stub"));
+}
+
+#[test]
+fn quick_matches() {
+    assert_eq!(quick(), omst().ok());
+
+    // concurrent calls share the lookup in flight
+    let calls: Vec<_> = (0..4).map(|_| thread::spawn(quick)).collect();
+    for call in calls {
+        assert_eq!(call.join().unwrap(), omst().ok());
+    }
+    assert_eq!(quick(), omst().ok());
+    assert_eq!(
+        quick_or(Permissions::Guest),
+        omst().unwrap_or(Permissions::Guest)
+    );
+}
+
+#[test]
+fn timeout() {
+    let slow = with_timeout(Duration::from_millis(10), || {
+        thread::sleep(Duration::from_secs(1));
+        Ok(())
+    });
+    assert_eq!(slow.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    assert!(with_timeout(Duration::from_secs(5), omst).is_ok());
+}
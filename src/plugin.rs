@@ -0,0 +1,229 @@
+//! Loader for external classification backends.
+//!
+//! Plugins are shared libraries (`.so`, `.dylib`, or `.dll`) which export two functions with the C
+//! calling convention:
+//!
+//! ```c
+//! /* Version of the plugin ABI implemented by the plugin, which must be 1. */
+//! uint32_t omst_plugin_abi(void);
+//!
+//! /* Classifies a user, given as a nul-terminated UTF-8 name, or NULL for the current user.
+//!  *
+//!  * On success, writes the symbol of the level (one of '%', '$', '&', '@', or '#') to `level` and
+//!  * returns 0. Otherwise, returns -1 if the user doesn't exist, an OS error code (like an errno
+//!  * value or Windows error code) if one describes the failure, or any other negative value. */
+//! int32_t omst_plugin_classify(const char *user, uint8_t *level);
+//! ```
+//!
+//! Since lookups may run on another thread (see [`with_timeout`](crate::with_timeout)),
+//! `omst_plugin_classify` must be thread-safe.
+use crate::Permissions;
+use std::ffi::{c_char, c_void, CString};
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::ptr;
+
+/// Version of the plugin ABI supported by this crate.
+pub const ABI: u32 = 1;
+
+/// Signature of `omst_plugin_abi`.
+type AbiFn = unsafe extern "C" fn() -> u32;
+
+/// Signature of `omst_plugin_classify`.
+type ClassifyFn = unsafe extern "C" fn(*const c_char, *mut u8) -> i32;
+
+/// Loaded plugin.
+///
+/// The library is unloaded when this is dropped.
+pub struct Plugin {
+    /// Handle returned by `dlopen` or `LoadLibraryExW`.
+    handle: *mut c_void,
+
+    /// `omst_plugin_classify`.
+    classify: ClassifyFn,
+}
+
+// plugins are required to be thread-safe
+unsafe impl Send for Plugin {}
+unsafe impl Sync for Plugin {}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugin")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Plugin {
+    /// Loads a plugin from a shared library.
+    ///
+    /// Fails with [`ErrorKind::InvalidData`] if the library doesn't export the plugin functions,
+    /// or implements a different version of the ABI.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization code, and the exported functions must have the
+    /// signatures documented in the [module documentation](self), so only trusted libraries should
+    /// be loaded.
+    pub unsafe fn load<P: AsRef<Path>>(path: P) -> io::Result<Plugin> {
+        let path = path.as_ref();
+        let handle = unsafe { sys::open(path)? };
+        let invalid = |message: String| {
+            unsafe { sys::close(handle) };
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} is not an omst plugin: {message}", path.display()),
+            )
+        };
+
+        let Some(abi) = (unsafe { sys::symbol(handle, c"omst_plugin_abi") }) else {
+            return Err(invalid("missing omst_plugin_abi".to_owned()));
+        };
+        let Some(classify) = (unsafe { sys::symbol(handle, c"omst_plugin_classify") }) else {
+            return Err(invalid("missing omst_plugin_classify".to_owned()));
+        };
+        let abi = unsafe { std::mem::transmute::<*mut c_void, AbiFn>(abi) };
+        let classify = unsafe { std::mem::transmute::<*mut c_void, ClassifyFn>(classify) };
+
+        let version = unsafe { abi() };
+        if version != ABI {
+            return Err(invalid(format!(
+                "implements ABI version {version}, but only {ABI} is supported"
+            )));
+        }
+        Ok(Plugin { handle, classify })
+    }
+
+    /// Determines the [`Permissions`] of the current user.
+    #[inline]
+    pub fn omst(&self) -> io::Result<Permissions> {
+        self.call(None)
+    }
+
+    /// Determines the [`Permissions`] of an arbitrary user, by name.
+    pub fn of_user(&self, name: &str) -> io::Result<Permissions> {
+        let name = CString::new(name).map_err(|_| {
+            io::Error::new(ErrorKind::NotFound, format!("user {name} does not exist"))
+        })?;
+        self.call(Some(&name))
+    }
+
+    /// Calls `omst_plugin_classify`.
+    fn call(&self, name: Option<&CString>) -> io::Result<Permissions> {
+        let mut level = 0;
+        let code =
+            unsafe { (self.classify)(name.map_or(ptr::null(), |name| name.as_ptr()), &mut level) };
+        match code {
+            0 => Permissions::from_byte(level).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "plugin returned an invalid level ({})",
+                        level.escape_ascii()
+                    ),
+                )
+            }),
+            -1 => Err(io::Error::new(
+                ErrorKind::NotFound,
+                match name {
+                    Some(name) => format!("user {} does not exist", name.to_string_lossy()),
+                    None => "current user does not exist".to_owned(),
+                },
+            )),
+            code if code > 0 => Err(io::Error::from_raw_os_error(code)),
+            code => Err(io::Error::other(format!("plugin failed with code {code}"))),
+        }
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        unsafe { sys::close(self.handle) }
+    }
+}
+
+#[cfg(not(windows))]
+mod sys {
+    use std::ffi::{c_void, CStr, CString};
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Loads a library with `dlopen`.
+    pub unsafe fn open(path: &Path) -> io::Result<*mut c_void> {
+        let cpath = CString::new(path.as_os_str().as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let handle = unsafe { libc::dlopen(cpath.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            let message = unsafe { libc::dlerror() };
+            let message = if message.is_null() {
+                format!("could not load {}", path.display())
+            } else {
+                unsafe { CStr::from_ptr(message) }
+                    .to_string_lossy()
+                    .into_owned()
+            };
+            return Err(io::Error::other(message));
+        }
+        Ok(handle)
+    }
+
+    /// Looks up a symbol with `dlsym`.
+    pub unsafe fn symbol(handle: *mut c_void, name: &CStr) -> Option<*mut c_void> {
+        let symbol = unsafe { libc::dlsym(handle, name.as_ptr()) };
+        (!symbol.is_null()).then_some(symbol)
+    }
+
+    /// Unloads a library with `dlclose`.
+    pub unsafe fn close(handle: *mut c_void) {
+        unsafe { libc::dlclose(handle) };
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::{c_void, CStr};
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use std::ptr;
+    use windows_sys::Win32::Foundation::FreeLibrary;
+    use windows_sys::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryExW};
+
+    /// Loads a library with `LoadLibraryExW`.
+    pub unsafe fn open(path: &Path) -> io::Result<*mut c_void> {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+        let handle = unsafe { LoadLibraryExW(wide.as_ptr(), ptr::null_mut(), 0) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(handle)
+    }
+
+    /// Looks up a symbol with `GetProcAddress`.
+    pub unsafe fn symbol(handle: *mut c_void, name: &CStr) -> Option<*mut c_void> {
+        unsafe { GetProcAddress(handle, name.as_ptr().cast()) }.map(|proc| proc as *mut c_void)
+    }
+
+    /// Unloads a library with `FreeLibrary`.
+    pub unsafe fn close(handle: *mut c_void) {
+        unsafe { FreeLibrary(handle) };
+    }
+}
+
+#[test]
+fn not_a_plugin() {
+    assert!(unsafe { Plugin::load("/nonexistent/libomst_plugin.so") }.is_err());
+
+    #[cfg(target_os = "linux")]
+    {
+        let err = unsafe { Plugin::load("libc.so.6") }.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "libc.so.6 is not an omst plugin: missing omst_plugin_abi"
+        );
+    }
+}
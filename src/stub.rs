@@ -0,0 +1,66 @@
+use crate::{Account, BackendInfo, Cost, Permissions};
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+#[cfg(not(windows))]
+pub use crate::shadow::{Report, UidRange as Level};
+#[cfg(windows)]
+pub use crate::winapi::{Priv as Level, Report};
+
+/// Information about this implementation.
+pub const BACKEND: BackendInfo = BackendInfo::new("stub", &[], Cost::Pure);
+
+/// Result returned for every user, which is always [`Permissions::User`](crate::Permissions::User).
+#[cfg(not(windows))]
+pub const LEVEL: Level = Level::InRange;
+
+/// Result returned for every user, which is always [`Permissions::User`](crate::Permissions::User).
+#[cfg(windows)]
+pub const LEVEL: Level = Level::User;
+
+/// Error returned for reports, since they describe how the OS was consulted.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Unsupported;
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("reports are unavailable with the stub backend")
+    }
+}
+impl StdError for Unsupported {}
+impl From<Unsupported> for io::Error {
+    #[inline]
+    fn from(err: Unsupported) -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, err)
+    }
+}
+
+/// Always returns [`LEVEL`], which can't elevate.
+#[inline]
+pub fn permissions() -> Result<Permissions, Unsupported> {
+    Ok(LEVEL.into())
+}
+
+/// Always returns [`LEVEL`], whether or not the user exists.
+#[inline]
+pub fn permissions_of_user(_: &str) -> Result<Permissions, Unsupported> {
+    Ok(LEVEL.into())
+}
+
+/// Always returns an empty list, since there's no user database to read.
+#[inline]
+pub fn accounts() -> Result<Vec<Account>, Unsupported> {
+    Ok(Vec::new())
+}
+
+/// Always fails with [`Unsupported`].
+#[inline]
+pub fn report() -> Result<Report, Unsupported> {
+    Err(Unsupported)
+}
+
+/// Always fails with [`Unsupported`].
+#[inline]
+pub fn report_of_user(_: &str) -> Result<Report, Unsupported> {
+    Err(Unsupported)
+}
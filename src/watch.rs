@@ -0,0 +1,213 @@
+use crate::{omst, Permissions};
+use std::io;
+use std::ops::ControlFlow;
+use std::thread;
+use std::time::Duration;
+#[cfg(not(windows))]
+use std::{fs, os::unix::fs::MetadataExt, time::SystemTime};
+
+/// What prompted a [`Watcher`] to report a change.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum Cause {
+    /// The first check, which always reports the level.
+    Start,
+
+    /// The effective user ID changed, like after `seteuid`.
+    Uid,
+
+    /// `/etc/login.defs`, `/etc/passwd`, or `/etc/group` was modified, replaced, created, or
+    /// removed.
+    Files,
+
+    /// The groups of the process changed, like after `setgroups`.
+    Groups,
+
+    /// A periodic recheck, used where the inputs can't be checked cheaply, like the process token
+    /// on Windows.
+    Recheck,
+}
+
+/// Change in the current user's level, as reported by a [`Watcher`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Event {
+    /// The new level, or the error that replaced it.
+    pub level: io::Result<Permissions>,
+
+    /// What prompted the check.
+    pub cause: Cause,
+}
+
+/// Watches the current user's level for changes, for long-running programs like shells, daemons,
+/// and status bars.
+///
+/// The inputs of the backend are checked every interval, and the level is only determined again if
+/// they changed: on unix-family systems, these are the effective user ID, the groups of the
+/// process, and the metadata of `/etc/login.defs`, `/etc/passwd`, and `/etc/group`. On Windows, the
+/// process token can change at any time, so the level is determined again on every check.
+///
+/// Events are only reported when the level changes, or an error replaces it (or vice versa), and
+/// the first check always reports the level. Since the effective user ID and impersonation tokens
+/// can be per-thread, [`spawn`](Watcher::spawn) only sees changes made to the whole process.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// for event in omst::Watcher::new(Duration::from_secs(1)) {
+///     println!("{:?} ({:?})", event.level, event.cause);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Watcher {
+    /// Time between checks.
+    interval: Duration,
+
+    /// Inputs as of the last check.
+    inputs: Option<Inputs>,
+
+    /// Result of the last check, with errors reduced to their kind so that they can be compared.
+    last: Option<Result<Permissions, io::ErrorKind>>,
+}
+impl Watcher {
+    /// Creates a watcher which checks every `interval`.
+    #[inline]
+    pub fn new(interval: Duration) -> Watcher {
+        Watcher {
+            interval,
+            inputs: None,
+            last: None,
+        }
+    }
+
+    /// Checks once, without blocking, returning an event if the level changed.
+    pub fn poll(&mut self) -> Option<Event> {
+        let inputs = Inputs::read();
+        let cause = match &self.inputs {
+            None => Cause::Start,
+            Some(old) => old.changed(&inputs)?,
+        };
+        self.inputs = Some(inputs);
+
+        let level = omst();
+        let last = level.as_ref().map(|level| *level).map_err(io::Error::kind);
+        if self.last == Some(last) {
+            return None;
+        }
+        self.last = Some(last);
+        Some(Event { level, cause })
+    }
+
+    /// Blocks until the level changes, checking every interval.
+    pub fn wait(&mut self) -> Event {
+        loop {
+            if let Some(event) = self.poll() {
+                return event;
+            }
+            thread::sleep(self.interval);
+        }
+    }
+
+    /// Calls `callback` with each change on another thread, until it returns
+    /// [`ControlFlow::Break`].
+    pub fn spawn<F>(mut self, mut callback: F) -> io::Result<thread::JoinHandle<()>>
+    where
+        F: FnMut(Event) -> ControlFlow<()> + Send + 'static,
+    {
+        thread::Builder::new()
+            .name("omst watcher".to_owned())
+            .spawn(move || while callback(self.wait()).is_continue() {})
+    }
+}
+impl Iterator for Watcher {
+    type Item = Event;
+
+    /// Blocks until the level changes, like [`wait`](Watcher::wait); this never returns `None`.
+    #[inline]
+    fn next(&mut self) -> Option<Event> {
+        Some(self.wait())
+    }
+}
+
+/// Inputs of the backend which can be checked cheaply.
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Inputs {
+    /// Effective user ID.
+    #[cfg(not(windows))]
+    euid: u32,
+
+    /// Effective and supplementary group IDs.
+    #[cfg(not(windows))]
+    groups: Vec<u32>,
+
+    /// Modification time, size, and inode of each file, if it exists.
+    #[cfg(not(windows))]
+    files: [Option<(SystemTime, u64, u64)>; 3],
+}
+impl Inputs {
+    /// Reads the current inputs.
+    #[cfg(not(windows))]
+    fn read() -> Inputs {
+        let stat = |path| {
+            let meta = fs::metadata(path).ok()?;
+            Some((meta.modified().ok()?, meta.len(), meta.ino()))
+        };
+        Inputs {
+            euid: crate::shadow::euid(),
+            groups: crate::shadow::process_groups(),
+            files: [
+                stat("/etc/login.defs"),
+                stat("/etc/passwd"),
+                stat("/etc/group"),
+            ],
+        }
+    }
+
+    /// Reads the current inputs, of which there are none.
+    #[cfg(windows)]
+    fn read() -> Inputs {
+        Inputs {}
+    }
+
+    /// Why the level might have changed, if the inputs differ.
+    #[cfg(not(windows))]
+    fn changed(&self, new: &Inputs) -> Option<Cause> {
+        if self.euid != new.euid {
+            Some(Cause::Uid)
+        } else if self.groups != new.groups {
+            Some(Cause::Groups)
+        } else if self.files != new.files {
+            Some(Cause::Files)
+        } else {
+            None
+        }
+    }
+
+    /// Why the level might have changed, which is always a recheck.
+    #[cfg(windows)]
+    fn changed(&self, _: &Inputs) -> Option<Cause> {
+        Some(Cause::Recheck)
+    }
+}
+
+#[test]
+fn unchanged() {
+    let mut watcher = Watcher::new(Duration::from_millis(10));
+    let event = watcher.poll().unwrap();
+    assert_eq!(event.cause, Cause::Start);
+    assert_eq!(event.level.unwrap(), omst().unwrap());
+    assert!(watcher.poll().is_none());
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    Watcher::new(Duration::from_millis(10))
+        .spawn(move |event| {
+            let _ = tx.send(event.cause);
+            ControlFlow::Break(())
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(rx.recv().unwrap(), Cause::Start);
+}